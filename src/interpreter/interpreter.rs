@@ -1,92 +1,77 @@
 use core::panic;
+use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::fs;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_complex::Complex;
+use num_rational::BigRational;
+use num_traits::{Pow, Signed, ToPrimitive, Zero};
 
 use crate::{
+    lexer::lexer::Lexer,
     node::node::{Node, NodeType},
-    token::token::Value,
+    parser::parser::Parser,
+    token::token::{Closure, Frame, Generator, GeneratorStatus, LoopSource, Scope, Value},
+    unit::unit::{Dimension, Quantity, Unit},
 };
 
+// Unwind payload of the errors the interpreter raises itself. `try` only catches
+// these; any other panic is a bug and keeps unwinding.
+struct RuntimeError(String);
+
+// Raises a runtime error without going through the panic hook, so errors that a
+// `try` catches are not reported.
+macro_rules! runtime_error {
+    ($($arg:tt)*) => {
+        std::panic::resume_unwind(Box::new(RuntimeError(format!($($arg)*))))
+    };
+}
+
 struct ScopeManager {
-    scopes: Vec<HashMap<String, Value>>,
-    num: i32,
+    scopes: Vec<Scope>,
 }
 
 impl ScopeManager {
     fn new() -> ScopeManager {
-        let scopes = vec![HashMap::new()];
-        ScopeManager { scopes, num: 1 }
+        let scopes = vec![Rc::new(RefCell::new(HashMap::new()))];
+        ScopeManager { scopes }
     }
 
     fn insert_identifier(&mut self, identifier: String, value: Value) {
-        let top = self.scopes.get_mut((self.num - 1) as usize).unwrap();
-        top.insert(identifier, value);
+        let top = self.scopes.last().unwrap();
+        top.borrow_mut().insert(identifier, value);
     }
 
     fn reassign_identifier(&mut self, identifier: String, value: Value) {
-        for scope in self.scopes.iter_mut().rev() {
+        for scope in self.scopes.iter().rev() {
+            let mut scope = scope.borrow_mut();
             if scope.contains_key(&identifier) {
-                let last_value = scope.get(&identifier).unwrap().clone();
-
-                if let Value::Boolean(_) = last_value {
-                    if let Value::Boolean(_) = value {
-                        scope.insert(identifier, value);
-                        return;
-                    } else {
-                        panic!("Type mismatch");
-                    }
-                }
-                if let Value::Number(_) = last_value {
-                    if let Value::Number(_) = value {
-                        scope.insert(identifier, value);
-                        return;
-                    } else {
-                        panic!("Type mismatch");
-                    }
-                }
-                if let Value::String(_) = last_value {
-                    if let Value::String(_) = value {
-                        scope.insert(identifier, value);
-                        return;
-                    } else {
-                        panic!("Type mismatch");
-                    }
-                }
-                if let Value::List(_) = last_value {
-                    if let Value::List(_) = value {
-                        scope.insert(identifier, value);
-                        return;
-                    } else {
-                        panic!("Type mismatch");
-                    }
-                }
-                if let Value::Map(_) = last_value {
-                    if let Value::Map(_) = value {
-                        scope.insert(identifier, value);
-                        return;
-                    } else {
-                        panic!("Type mismatch");
-                    }
-                }
-
+                let last_value = scope.get(&identifier).unwrap();
+                let value = check_reassignment(last_value, value);
                 scope.insert(identifier, value);
                 return;
             }
         }
-        panic!("Identifier not found");
+        runtime_error!("Identifier not found");
     }
 
     fn get_identifier(&self, identifier: &str) -> Value {
         for scope in self.scopes.iter().rev() {
-            if scope.contains_key(identifier) {
-                return scope.get(identifier).unwrap().clone();
+            if let Some(value) = scope.borrow().get(identifier) {
+                return value.clone();
             }
         }
-        panic!("Identifier not found");
+        runtime_error!("Identifier not found");
     }
 
     fn contains_identifier(&self, identifier: &str) -> bool {
         for scope in self.scopes.iter().rev() {
-            if scope.contains_key(identifier) {
+            if scope.borrow().contains_key(identifier) {
                 return true;
             }
         }
@@ -101,44 +86,338 @@ impl ScopeManager {
     }
 
     fn new_scope(&mut self) {
-        self.scopes.push(HashMap::new());
-        self.num += 1;
+        self.scopes.push(Rc::new(RefCell::new(HashMap::new())));
     }
 
     fn pop_scope(&mut self) {
         self.scopes.pop();
-        self.num -= 1;
+    }
+
+    fn capture(&self) -> Vec<Scope> {
+        self.scopes.clone()
+    }
+
+    fn enter_environment(&mut self, environment: Vec<Scope>) -> Vec<Scope> {
+        std::mem::replace(&mut self.scopes, environment)
+    }
+
+    fn restore_environment(&mut self, scopes: Vec<Scope>) {
+        self.scopes = scopes;
+    }
+}
+
+fn check_reassignment(last_value: &Value, value: Value) -> Value {
+    if let Value::Boolean(_) = last_value {
+        if let Value::Boolean(_) = value {
+            return value;
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Number(_) | Value::BigNumber(_) = last_value {
+        if let Value::Number(_) | Value::BigNumber(_) = value {
+            return value;
+        } else if let Value::Decimal(val) = &value {
+            // In decimal mode whole numbers are decimals, so `i = i + 1` stays an int.
+            if val.is_integer() {
+                let val = val.to_integer();
+                return match val.to_i32() {
+                    Some(val) => Value::Number(val),
+                    None => Value::BigNumber(val),
+                };
+            }
+            runtime_error!("Type mismatch");
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Float(_) = last_value {
+        if let Value::Float(_) = value {
+            return value;
+        } else if let Value::Number(val) = value {
+            return Value::Float(val as f32);
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Rational(_) = last_value {
+        if let Value::Rational(_) = value {
+            return value;
+        } else if let Value::Number(val) = value {
+            return Value::Rational(BigInt::from(val).into());
+        } else if let Value::BigNumber(val) = value {
+            return Value::Rational(val.into());
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Decimal(_) = last_value {
+        if let Value::Decimal(_) = value {
+            return value;
+        } else if let Value::Number(val) = value {
+            return Value::Decimal(BigInt::from(val).into());
+        } else if let Value::BigNumber(val) = value {
+            return Value::Decimal(val.into());
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Complex(_) = last_value {
+        let real = match &value {
+            Value::Complex(_) => return value,
+            Value::Number(val) => *val as f32,
+            Value::BigNumber(val) => val
+                .to_f32()
+                .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+            Value::Float(val) => *val,
+            Value::Rational(val) | Value::Decimal(val) => val
+                .to_f32()
+                .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+            _ => runtime_error!("Type mismatch"),
+        };
+        return Value::Complex(Complex::new(real, 0.0));
+    }
+    if let Value::Quantity(_) = last_value {
+        if let Value::Quantity(_) = value {
+            return value;
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::String(_) = last_value {
+        if let Value::String(_) = value {
+            return value;
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::List(_) = last_value {
+        if let Value::List(_) = value {
+            return value;
+        } else if let Value::Range { .. } = value {
+            return range_to_list(&value);
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Range { .. } = last_value {
+        if let Value::Range { .. } = value {
+            return value;
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Map(_) = last_value {
+        if let Value::Map(_) = value {
+            return value;
+        } else {
+            runtime_error!("Type mismatch");
+        }
+    }
+    if let Value::Struct { name, .. } = last_value {
+        if let Value::Struct { name: other, .. } = &value {
+            if name == other {
+                return value;
+            }
+        }
+        runtime_error!("Type mismatch");
+    }
+    if let Value::Enum { name, .. } = last_value {
+        if let Value::Enum { name: other, .. } = &value {
+            if name == other {
+                return value;
+            }
+        }
+        runtime_error!("Type mismatch");
+    }
+
+    value
+}
+
+const DEFAULT_PRECISION: u32 = 28;
+
+fn range_bounds(range: &Value) -> std::ops::Range<i64> {
+    if let Value::Range {
+        start,
+        end,
+        inclusive,
+    } = range
+    {
+        *start as i64..*end as i64 + *inclusive as i64
+    } else {
+        runtime_error!("Expected a range");
+    }
+}
+
+fn range_to_list(range: &Value) -> Value {
+    Value::List(
+        range_bounds(range)
+            .map(|i| Value::Number(i as i32))
+            .collect(),
+    )
+}
+
+fn declared_types(root: &Node, names: &mut Vec<String>) {
+    for child in &root.children {
+        if let NodeType::Struct | NodeType::Enum = child.node_type {
+            names.push(child.value.clone().expect("expected a type name"));
+        } else {
+            declared_types(child, names);
+        }
+    }
+}
+
+// Prefixes every use of a module's own types with its namespace, so two modules
+// can declare the same type name without colliding in the interpreter registries.
+fn qualify_types(root: &mut Node, names: &[String], namespace: &str) {
+    let qualify = |node: &mut Node| {
+        if let Some(name) = node.value.as_mut().filter(|name| names.contains(name)) {
+            *name = format!("{}.{}", namespace, name);
+        }
+    };
+
+    match root.node_type {
+        NodeType::Struct
+        | NodeType::Enum
+        | NodeType::StructLiteral
+        | NodeType::Variant
+        | NodeType::TypeAnnotation => qualify(root),
+        NodeType::Pattern if root.value.as_deref() == Some("variant") => {
+            qualify(&mut root.children[0])
+        }
+        _ => {}
+    }
+    for child in &mut root.children {
+        qualify_types(child, names, namespace);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ControlFlow {
+    None,
+    Return,
+    Break(Option<String>),
+    Continue(Option<String>),
+    Yield,
+}
+
+// A runtime error caught by `try`: either a thrown script value or an interpreter error.
+enum Failure {
+    Thrown(Value),
+    Error(String),
+}
+
+impl Failure {
+    fn to_value(&self) -> Value {
+        match self {
+            Failure::Thrown(value) => value.clone(),
+            Failure::Error(message) => Value::String(message.clone()),
+        }
     }
 }
 
+enum LoopStep {
+    Next,
+    Break,
+    Exit,
+}
+
 pub struct Interpreter {
     scope_manager: ScopeManager,
+    structs: HashMap<String, Vec<(String, String)>>,
+    enums: HashMap<String, Vec<(String, Vec<String>)>>,
+    modules: HashMap<PathBuf, Value>,
+    // Files currently being evaluated, the innermost import last.
+    importing: Vec<PathBuf>,
+    thrown: Option<Value>,
+    // Resume frames of the generator being suspended or resumed.
+    frames: Vec<Frame>,
+    resuming: bool,
+    yielded: Option<(Value, Vec<Scope>)>,
+    precision: Option<u32>,
+    complex_mode: bool,
 }
 
 impl Interpreter {
     pub fn new() -> Interpreter {
         let scope_manager = ScopeManager::new();
-        Interpreter { scope_manager }
+        Interpreter {
+            scope_manager,
+            structs: HashMap::new(),
+            enums: HashMap::new(),
+            modules: HashMap::new(),
+            importing: Vec::new(),
+            thrown: None,
+            frames: Vec::new(),
+            resuming: false,
+            yielded: None,
+            precision: None,
+            complex_mode: false,
+        }
+    }
+
+    /// Switches to decimal mode: every numeric literal becomes a `Value::Decimal`
+    /// rounded to `precision` fractional digits.
+    pub fn set_precision(&mut self, precision: u32) {
+        self.precision = Some(precision);
+    }
+
+    /// Records the file being run so `import` paths resolve relative to it.
+    pub fn set_path(&mut self, path: &str) {
+        let path = Path::new(path);
+        self.importing = vec![path.canonicalize().unwrap_or_else(|_| path.to_path_buf())];
+    }
+
+    /// Lets real operations such as `sqrt(-1)` produce a `Value::Complex` instead of failing.
+    pub fn set_complex_mode(&mut self, enabled: bool) {
+        self.complex_mode = enabled;
     }
 
     pub fn evaluate(&mut self, root: Node) -> Value {
-        self.store_functions(&root);
+        match std::panic::catch_unwind(AssertUnwindSafe(|| self.evaluate_program(&root))) {
+            Ok(value) => value,
+            Err(payload) => match payload.downcast::<RuntimeError>() {
+                Ok(error) => {
+                    self.thrown = None;
+                    panic!("{}", error.0)
+                }
+                Err(payload) => std::panic::resume_unwind(payload),
+            },
+        }
+    }
+
+    fn evaluate_program(&mut self, root: &Node) -> Value {
+        self.store_functions(root);
 
         let mut result = Value::Number(0);
-        let mut early_return = false;
+        let mut control_flow = ControlFlow::None;
         for child in &root.children {
-            result = self.evaluate_helper(child, &mut early_return);
-            if early_return {
-                break;
+            result = self.evaluate_helper(child, &mut control_flow);
+            match control_flow {
+                ControlFlow::None => {}
+                ControlFlow::Return => break,
+                ControlFlow::Break(_) | ControlFlow::Continue(_) => {
+                    runtime_error!("break or continue outside of a loop")
+                }
+                ControlFlow::Yield => runtime_error!("yield outside of a generator"),
             }
         }
         result
     }
 
-    fn evaluate_helper(&mut self, root: &Node, early_return: &mut bool) -> Value {
+    fn evaluate_helper(&mut self, root: &Node, control_flow: &mut ControlFlow) -> Value {
         match root.node_type {
             NodeType::FunctionCall => {
-                let val = root.value.as_ref().unwrap().as_str();
+                let Some(val) = root.value.as_ref() else {
+                    let function = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
+                    let values: Vec<Value> = root.children[0]
+                        .children
+                        .iter()
+                        .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
+                        .collect();
+                    return self.evaluate_function(&function, values);
+                };
+                let val = val.as_str();
                 if self.scope_manager.contains_identifier(val) {
                     return self.handle_function_call(root, val);
                 } else {
@@ -154,10 +433,20 @@ impl Interpreter {
                         "is_string" => return self.handle_is_string(root),
                         "is_list" => return self.handle_is_list(root),
                         "is_function" => return self.handle_is_function(root), //TODO: fix this
+                        "rational" => return self.handle_rational(root),
+                        "to_float" => return self.handle_to_float(root),
+                        "to_list" => return self.handle_to_list(root),
+                        "to_str" => return self.handle_to_str(root),
+                        "next" => return self.handle_next(root),
+                        "sqrt" => return self.handle_sqrt(root),
+                        "abs" => return self.handle_abs(root),
+                        "to" => return self.handle_to(root),
+                        "bin" => return self.handle_radix_format(root, 2, "0b"),
+                        "hex" => return self.handle_radix_format(root, 16, "0x"),
 
                         "input" => return self.handle_input(),
                         _ => {
-                            panic!("Function not found");
+                            runtime_error!("Function not found");
                         }
                     }
                 }
@@ -171,8 +460,45 @@ impl Interpreter {
             NodeType::Declaration => {
                 return self.handle_declaration(root);
             }
+            NodeType::Struct | NodeType::Enum => {
+                // type declarations are registered up front by store_functions
+                return Value::Null;
+            }
+            NodeType::Variant => {
+                return self.handle_variant(root);
+            }
+            NodeType::Import => {
+                return self.handle_import(root);
+            }
+            NodeType::Try => {
+                return self.handle_try(root, control_flow);
+            }
+            NodeType::Yield => {
+                // Reaching the suspended yield again completes the resume.
+                if self.resuming {
+                    self.resuming = false;
+                    return Value::Null;
+                }
+                let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+                self.yielded = Some((value.clone(), self.scope_manager.capture()));
+                *control_flow = ControlFlow::Yield;
+                return value;
+            }
+            NodeType::Throw => {
+                let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+                self.raise(value);
+            }
+            NodeType::StructLiteral => {
+                return self.handle_struct_literal(root);
+            }
+            NodeType::Function => {
+                return Value::Function(Closure {
+                    function: root.clone(),
+                    environment: self.scope_manager.capture(),
+                });
+            }
             NodeType::Block => {
-                return self.handle_block(root, early_return);
+                return self.handle_block(root, control_flow);
             }
             NodeType::List => {
                 return self.handle_list(root);
@@ -181,10 +507,33 @@ impl Interpreter {
                 return self.handle_map(root);
             }
             NodeType::If => {
-                return self.handle_if(root, early_return);
+                return self.handle_if(root, control_flow);
             }
             NodeType::While => {
-                return self.handle_while(root, early_return);
+                return self.handle_while(root, None, control_flow);
+            }
+            NodeType::For => {
+                return self.handle_for(root, None, control_flow);
+            }
+            NodeType::Label => {
+                let label = root.value.as_ref();
+                let inner = &root.children[0];
+                match inner.node_type {
+                    NodeType::While => return self.handle_while(inner, label, control_flow),
+                    NodeType::For => return self.handle_for(inner, label, control_flow),
+                    _ => runtime_error!("Labels can only be applied to loops"),
+                }
+            }
+            NodeType::Match => {
+                return self.handle_match(root, control_flow);
+            }
+            NodeType::Break => {
+                *control_flow = ControlFlow::Break(root.value.clone());
+                return Value::Null;
+            }
+            NodeType::Continue => {
+                *control_flow = ControlFlow::Continue(root.value.clone());
+                return Value::Null;
             }
             NodeType::Index => {
                 return self.handle_index(root);
@@ -198,37 +547,45 @@ impl Interpreter {
             NodeType::Identifier | NodeType::Literal => {
                 return self.parse_value(root);
             }
+            NodeType::Unit => {
+                let unit = Unit::parse(root.value.as_ref().expect("expected a unit"));
+                return Value::Quantity(Quantity {
+                    value: unit.factor,
+                    unit,
+                });
+            }
             NodeType::Factor | NodeType::Term | NodeType::Expression | NodeType::Args => {
                 // just wrapper nodes
                 if root.children.len() != 1 {
-                    panic!("Invalid number of children for {:?}", root.node_type);
+                    runtime_error!("Invalid number of children for {:?}", root.node_type);
                 }
-                return self.evaluate_helper(&root.children[0], early_return);
+                return self.evaluate_helper(&root.children[0], control_flow);
             }
             NodeType::Return => {
-                *early_return = true;
+                *control_flow = ControlFlow::Return;
                 if root.children.len() == 0 {
                     return Value::Null;
                 }
-                return self.evaluate_helper(&root.children[0], early_return);
+                return self.evaluate_helper(&root.children[0], control_flow);
             }
             NodeType::Parameters => {
                 // just wrapper nodes
-                panic!("Parameters node should not be evaluated");
+                runtime_error!("Parameters node should not be evaluated");
             }
             NodeType::Program => {
                 // just wrapper nodes
-                panic!("Program node should not be evaluated");
+                runtime_error!("Program node should not be evaluated");
             }
 
             _ => {
-                panic!("Invalid node type {:?}", root.node_type);
+                runtime_error!("Invalid node type {:?}", root.node_type);
             }
         }
     }
 
     fn evaluate_function(&mut self, function: &Value, parameter_values: Vec<Value>) -> Value {
-        if let Value::Function(function) = function {
+        if let Value::Function(closure) = function {
+            let function = &closure.function;
             let param_names: Vec<String> = function.children[0]
                 .children
                 .iter()
@@ -258,45 +615,85 @@ impl Interpreter {
 
             let mut arg_values = HashMap::new();
             for (i, param_name) in param_names.iter().enumerate() {
-                let value = parameter_values[i].clone();
+                let mut value = parameter_values[i].clone();
                 let type_annotation = param_type_annotations[i].clone();
                 if let Value::Boolean(_) = value {
                     if type_annotation != "bool" {
-                        panic!("Type mismatch");
+                        runtime_error!("Type mismatch");
                     }
-                } else if let Value::Number(_) = value {
-                    if type_annotation != "int" {
-                        panic!("Type mismatch");
+                } else if let Value::Number(_)
+                | Value::BigNumber(_)
+                | Value::Float(_)
+                | Value::Rational(_)
+                | Value::Decimal(_)
+                | Value::Complex(_) = value
+                {
+                    value = self.coerce_number(value, &type_annotation);
+                } else if let Value::Quantity(_) = value {
+                    if type_annotation != "quantity" {
+                        runtime_error!("Type mismatch");
                     }
                 } else if let Value::String(_) = value {
                     if type_annotation != "str" {
-                        panic!("Type mismatch");
+                        runtime_error!("Type mismatch");
                     }
                 } else if let Value::List(_) = value {
                     if type_annotation != "list" {
-                        panic!("Type mismatch");
+                        runtime_error!("Type mismatch");
+                    }
+                } else if let Value::Range { .. } = value {
+                    if type_annotation == "list" {
+                        value = range_to_list(&value);
+                    } else if type_annotation != "range" {
+                        runtime_error!("Type mismatch");
                     }
                 } else if let Value::Function(_) = value {
                     if type_annotation != "function" {
-                        panic!("Type mismatch");
+                        runtime_error!("Type mismatch");
+                    }
+                } else if let Value::Generator(_) = value {
+                    if type_annotation != "generator" {
+                        runtime_error!("Type mismatch");
+                    }
+                } else if let Value::Struct { name, .. } | Value::Enum { name, .. } = &value {
+                    if type_annotation != *name {
+                        runtime_error!("Type mismatch");
                     }
                 } else {
-                    panic!("Invalid type");
+                    runtime_error!("Invalid type");
                 }
-                arg_values.insert(param_name.clone(), parameter_values[i].clone());
+                arg_values.insert(param_name.clone(), value);
             }
 
+            let caller_scopes = self
+                .scope_manager
+                .enter_environment(closure.environment.clone());
             self.scope_manager.new_scope_with_values(arg_values);
-            let mut early_return = false;
+            if function.value.as_deref() == Some("generator") {
+                let scopes = self.scope_manager.capture();
+                self.scope_manager.restore_environment(caller_scopes);
+                return Value::Generator(Generator::new(
+                    Rc::new(function.children[1].clone()),
+                    scopes,
+                ));
+            }
+            let mut control_flow = ControlFlow::None;
             for child in function.children[1]
                 .children
                 .iter()
                 .take(function.children[1].children.len() - 1)
             {
-                let result = self.evaluate_helper(child, &mut early_return);
-                if early_return {
-                    self.scope_manager.pop_scope();
-                    return result;
+                let result = self.evaluate_helper(child, &mut control_flow);
+                match control_flow {
+                    ControlFlow::None => {}
+                    ControlFlow::Return => {
+                        self.scope_manager.restore_environment(caller_scopes);
+                        return result;
+                    }
+                    ControlFlow::Break(_) | ControlFlow::Continue(_) => {
+                        runtime_error!("break or continue outside of a loop")
+                    }
+                    ControlFlow::Yield => runtime_error!("yield outside of a generator"),
                 }
             }
 
@@ -305,30 +702,47 @@ impl Interpreter {
                     .children
                     .last()
                     .expect("expected a child"),
-                &mut false,
+                &mut control_flow,
             );
+            if let ControlFlow::Break(_) | ControlFlow::Continue(_) = control_flow {
+                runtime_error!("break or continue outside of a loop");
+            }
+            if control_flow == ControlFlow::Yield {
+                runtime_error!("yield outside of a generator");
+            }
 
-            self.scope_manager.pop_scope();
+            self.scope_manager.restore_environment(caller_scopes);
 
             return result;
         } else {
-            panic!("Expected a function");
+            runtime_error!("Expected a function");
         }
     }
 
     fn parse_value(&self, node: &Node) -> Value {
         if node.node_type == NodeType::Literal {
             let value = node.value.as_ref().unwrap();
-            if value.contains("\"") {
-                return Value::String(value.clone().replace("\"", ""));
-            } else if value.contains(".") {
-                return Value::Float(value.parse().unwrap());
+            if let Some(string) = value.strip_prefix('"') {
+                return Value::String(string.strip_suffix('"').unwrap_or(string).to_string());
             } else if value == "true" {
                 return Value::Boolean(true);
             } else if value == "false" {
                 return Value::Boolean(false);
+            } else if let Some(radix) = self.literal_radix(value) {
+                let digits = &value.as_bytes()[2..];
+                let value = BigInt::parse_bytes(digits, radix).expect("Invalid number literal");
+                return self.normalize_big_number(value);
+            } else if let Some(imaginary) = value.strip_suffix('i') {
+                let imaginary = imaginary.parse().expect("Invalid imaginary literal");
+                return Value::Complex(Complex::new(0.0, imaginary));
+            } else if self.precision.is_some() {
+                return Value::Decimal(self.round_decimal(self.parse_decimal(value)));
+            } else if value.contains(['.', 'e', 'E']) {
+                return Value::Float(value.parse().expect("Invalid float literal"));
+            } else if let Ok(value) = value.parse() {
+                return Value::Number(value);
             } else {
-                return Value::Number(value.parse().unwrap());
+                return Value::BigNumber(value.parse().expect("Invalid number literal"));
             }
         } else if node.node_type == NodeType::Identifier {
             return self
@@ -340,102 +754,221 @@ impl Interpreter {
                 )
                 .clone();
         } else {
-            panic!("Invalid value {:?}", node.node_type);
+            runtime_error!("Invalid value {:?}", node.node_type);
+        }
+    }
+
+    fn literal_radix(&self, literal: &str) -> Option<u32> {
+        match literal.get(..2)?.to_lowercase().as_str() {
+            "0x" => Some(16),
+            "0b" => Some(2),
+            "0o" => Some(8),
+            _ => None,
+        }
+    }
+
+    fn parse_decimal(&self, literal: &str) -> BigRational {
+        let (mantissa, exponent) = match literal.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (
+                mantissa,
+                exponent.parse::<i32>().expect("Invalid decimal literal"),
+            ),
+            None => (literal, 0),
+        };
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits: BigInt = format!("{}{}", whole, fraction)
+            .parse()
+            .expect("Invalid decimal literal");
+
+        let scale = exponent - fraction.len() as i32;
+        let factor = BigRational::from_integer(BigInt::from(10).pow(scale.unsigned_abs()));
+        if scale < 0 {
+            BigRational::from_integer(digits) / factor
+        } else {
+            BigRational::from_integer(digits) * factor
+        }
+    }
+
+    fn round_decimal(&self, value: BigRational) -> BigRational {
+        let precision = self.precision.unwrap_or(DEFAULT_PRECISION);
+        let factor = BigRational::from_integer(BigInt::from(10).pow(precision));
+        (value * &factor).round() / factor
+    }
+
+    fn format_decimal(&self, value: &BigRational) -> String {
+        let precision = self.precision.unwrap_or(DEFAULT_PRECISION) as usize;
+        let factor = BigInt::from(10).pow(precision as u32);
+        let digits = (value.abs() * BigRational::from_integer(factor))
+            .to_integer()
+            .to_string();
+        let digits = format!("{:0>width$}", digits, width = precision + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - precision);
+        let fraction = fraction.trim_end_matches('0');
+
+        let sign = if value.is_negative() { "-" } else { "" };
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}.{}", sign, whole, fraction)
         }
     }
 
     fn print_value(&self, value: &Value) {
+        print!("{}", self.format_value(value));
+    }
+
+    fn format_value(&self, value: &Value) -> String {
         match value {
-            Value::Number(val) => print!("{}", val),
-            Value::Float(val) => print!("{}", val),
-            Value::String(val) => print!("{}", val),
-            Value::Boolean(val) => print!("{}", val),
-            Value::Null => print!("null"),
-            Value::Function(_) => print!("function"),
-            Value::Map(val) => {
-                print!("{{");
-                for (i, (key, value)) in val.iter().enumerate() {
-                    print!("{}: ", key);
-                    self.print_value(value);
-                    if i < val.len() - 1 {
-                        print!(", ");
+            Value::Number(val) => val.to_string(),
+            Value::BigNumber(val) => val.to_string(),
+            Value::Float(val) => val.to_string(),
+            Value::Rational(val) => val.to_string(),
+            Value::Decimal(val) => self.format_decimal(val),
+            Value::Quantity(val) => {
+                format!("{} {}", (val.value / val.unit.factor) as f32, val.unit.name)
+            }
+            Value::Complex(val) => {
+                // A unit imaginary part prints as plain `i`, so `sqrt(-1)` is `i`.
+                let imaginary = |im: f32| {
+                    if im == 1.0 {
+                        "i".to_string()
+                    } else {
+                        format!("{}i", im)
                     }
+                };
+                if val.re == 0.0 && val.im < 0.0 {
+                    format!("-{}", imaginary(-val.im))
+                } else if val.re == 0.0 {
+                    imaginary(val.im)
+                } else if val.im < 0.0 {
+                    format!("{}-{}", val.re, imaginary(-val.im))
+                } else {
+                    format!("{}+{}", val.re, imaginary(val.im))
                 }
-                print!("}}");
             }
+            Value::String(val) => val.clone(),
+            Value::Boolean(val) => val.to_string(),
+            Value::Null => "null".to_string(),
+            Value::Function(_) => "function".to_string(),
+            Value::Generator(_) => "generator".to_string(),
+            Value::Map(val) => {
+                let entries: Vec<String> = val
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, self.format_value(value)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Value::Range {
+                start,
+                end,
+                inclusive,
+            } => format!("{}{}{}", start, if *inclusive { "..=" } else { ".." }, end),
             Value::List(val) => {
-                print!("[");
-                for (i, v) in val.iter().enumerate() {
-                    self.print_value(v);
-                    if i < val.len() - 1 {
-                        print!(", ");
-                    }
+                let items: Vec<String> = val.iter().map(|v| self.format_value(v)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Struct { name, fields } => {
+                let entries: Vec<String> = fields
+                    .iter()
+                    .map(|(field, value)| format!("{}: {}", field, self.format_value(value)))
+                    .collect();
+                format!("{} {{ {} }}", name, entries.join(", "))
+            }
+            Value::Enum {
+                name,
+                variant,
+                payload,
+            } => {
+                if payload.is_empty() {
+                    return format!("{}.{}", name, variant);
                 }
-                print!("]");
+                let items: Vec<String> = payload.iter().map(|v| self.format_value(v)).collect();
+                format!("{}.{}({})", name, variant, items.join(", "))
             }
         }
     }
 
     fn handle_print(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         self.print_value(&value);
         return value;
     }
 
     fn handle_println(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         self.print_value(&value);
         println!();
         return value;
     }
 
     fn handle_head(&mut self, root: &Node) -> Value {
-        let list = self.evaluate_helper(&root.children[0], &mut false);
+        let list = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         if let Value::List(list) = list {
-            return list[0].clone();
+            return list
+                .first()
+                .cloned()
+                .unwrap_or_else(|| runtime_error!("Index out of range"));
         } else {
-            panic!("Expected a list");
+            runtime_error!("Expected a list");
         }
     }
 
     fn handle_tail(&mut self, root: &Node) -> Value {
-        let list = self.evaluate_helper(&root.children[0], &mut false);
+        let list = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         if let Value::List(list) = list {
-            return Value::List(list[1..].to_vec().clone());
+            if list.is_empty() {
+                runtime_error!("Index out of range");
+            }
+            return Value::List(list[1..].to_vec());
         } else if let Value::String(string) = list {
-            return Value::String(string[1..].to_string());
+            let mut chars = string.chars();
+            if chars.next().is_none() {
+                runtime_error!("Index out of range");
+            }
+            return Value::String(chars.as_str().to_string());
         } else {
-            panic!("Expected a list or a string");
+            runtime_error!("Expected a list or a string");
         }
     }
 
     fn handle_len(&mut self, root: &Node) -> Value {
-        let list = self.evaluate_helper(&root.children[0], &mut false);
+        let list = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         if let Value::List(list) = list {
             return Value::Number(list.len() as i32);
         } else if let Value::String(string) = list {
             return Value::Number(string.len() as i32);
+        } else if let Value::Range { .. } = list {
+            let bounds = range_bounds(&list);
+            let count = (bounds.end - bounds.start).max(0);
+            return self.normalize_big_number(BigInt::from(count));
         } else {
-            panic!("Expected a list or a string");
+            runtime_error!("Expected a list or a string");
         }
     }
 
     fn handle_type(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         return match value {
-            Value::Number(_) => Value::String("number".to_string()),
+            Value::Number(_) | Value::BigNumber(_) => Value::String("number".to_string()),
             Value::Float(_) => Value::String("float".to_string()),
+            Value::Rational(_) => Value::String("rational".to_string()),
+            Value::Decimal(_) => Value::String("decimal".to_string()),
+            Value::Complex(_) => Value::String("complex".to_string()),
+            Value::Quantity(_) => Value::String("quantity".to_string()),
             Value::String(_) => Value::String("string".to_string()),
             Value::Boolean(_) => Value::String("bool".to_string()),
             Value::List(_) => Value::String("list".to_string()),
+            Value::Range { .. } => Value::String("range".to_string()),
             Value::Function(_) => Value::String("function".to_string()),
+            Value::Generator(_) => Value::String("generator".to_string()),
             Value::Map(_) => Value::String("map".to_string()),
+            Value::Struct { name, .. } | Value::Enum { name, .. } => Value::String(name),
             Value::Null => Value::String("null".to_string()),
         };
     }
 
     fn handle_is_bool(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         return match value {
             Value::Boolean(_) => Value::Boolean(true),
             _ => Value::Boolean(false),
@@ -443,15 +976,20 @@ impl Interpreter {
     }
 
     fn handle_is_number(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         return match value {
-            Value::Number(_) => Value::Boolean(true),
+            Value::Number(_)
+            | Value::BigNumber(_)
+            | Value::Float(_)
+            | Value::Rational(_)
+            | Value::Decimal(_)
+            | Value::Complex(_) => Value::Boolean(true),
             _ => Value::Boolean(false),
         };
     }
 
     fn handle_is_string(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         return match value {
             Value::String(_) => Value::Boolean(true),
             _ => Value::Boolean(false),
@@ -459,7 +997,7 @@ impl Interpreter {
     }
 
     fn handle_is_list(&mut self, root: &Node) -> Value {
-        let value = self.evaluate_helper(&root.children[0], &mut false);
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
         return match value {
             Value::List(_) => Value::Boolean(true),
             _ => Value::Boolean(false),
@@ -470,7 +1008,7 @@ impl Interpreter {
         let args: Vec<Value> = root
             .children
             .iter()
-            .map(|child| self.evaluate_helper(child, &mut false))
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
             .collect();
         let val = &args[0];
         if let Value::Function(_) = val {
@@ -480,6 +1018,221 @@ impl Interpreter {
         }
     }
 
+    fn handle_rational(&mut self, root: &Node) -> Value {
+        let args: Vec<Value> = root.children[0]
+            .children
+            .iter()
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
+            .collect();
+
+        let numerator = match &args[0] {
+            Value::Float(val) => BigRational::from_float(*val)
+                .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+            val => self
+                .to_rational(val)
+                .unwrap_or_else(|| runtime_error!("Expected a number")),
+        };
+        if args.len() == 1 {
+            return Value::Rational(numerator);
+        }
+
+        let denominator = self
+            .to_rational(&args[1])
+            .unwrap_or_else(|| runtime_error!("Expected an integer"));
+        if denominator.is_zero() {
+            runtime_error!("Division by zero");
+        }
+        Value::Rational(numerator / denominator)
+    }
+
+    fn handle_to_float(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        Value::Float(self.to_float(&value))
+    }
+
+    fn handle_to_str(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        Value::String(self.format_value(&value))
+    }
+
+    fn handle_to_list(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        return match value {
+            Value::List(_) => value,
+            Value::Range { .. } => range_to_list(&value),
+            Value::String(string) => Value::List(
+                string
+                    .chars()
+                    .map(|c| Value::String(c.to_string()))
+                    .collect(),
+            ),
+            Value::Generator(generator) => {
+                let mut items = Vec::new();
+                while let Some(item) = self.resume_generator(&generator) {
+                    items.push(item);
+                }
+                Value::List(items)
+            }
+            _ => runtime_error!("Expected a list, range, string or generator"),
+        };
+    }
+
+    fn handle_next(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        let Value::Generator(generator) = value else {
+            runtime_error!("Expected a generator");
+        };
+        return self.resume_generator(&generator).unwrap_or(Value::Null);
+    }
+
+    fn resume_generator(&mut self, generator: &Generator) -> Option<Value> {
+        let (body, scopes, frames) = {
+            let mut state = generator.state.borrow_mut();
+            match state.status {
+                GeneratorStatus::Done => return None,
+                GeneratorStatus::Running => runtime_error!("Generator is already running"),
+                GeneratorStatus::Suspended => {}
+            }
+            state.status = GeneratorStatus::Running;
+            (
+                state.body.clone(),
+                std::mem::take(&mut state.scopes),
+                std::mem::take(&mut state.frames),
+            )
+        };
+
+        let caller_scopes = self.scope_manager.enter_environment(scopes);
+        let caller_frames = std::mem::replace(&mut self.frames, frames);
+        self.resuming = !self.frames.is_empty();
+
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut control_flow = ControlFlow::None;
+            self.handle_block(&body, &mut control_flow);
+            match control_flow {
+                ControlFlow::Yield => self.yielded.take(),
+                ControlFlow::Break(_) | ControlFlow::Continue(_) => {
+                    runtime_error!("break or continue outside of a loop")
+                }
+                ControlFlow::None | ControlFlow::Return => None,
+            }
+        }));
+
+        let frames = std::mem::replace(&mut self.frames, caller_frames);
+        self.scope_manager.restore_environment(caller_scopes);
+
+        let mut state = generator.state.borrow_mut();
+        // A generator that fails is finished; the error carries on to the caller.
+        let yielded = result.unwrap_or_else(|payload| {
+            state.status = GeneratorStatus::Done;
+            self.resuming = false;
+            std::panic::resume_unwind(payload)
+        });
+        match yielded {
+            Some((value, scopes)) => {
+                state.scopes = scopes;
+                state.frames = frames;
+                state.status = GeneratorStatus::Suspended;
+                Some(value)
+            }
+            None => {
+                state.status = GeneratorStatus::Done;
+                None
+            }
+        }
+    }
+
+    fn resume_frame(&mut self) -> Option<Frame> {
+        if !self.resuming {
+            return None;
+        }
+        Some(self.frames.pop().expect("expected a resume frame"))
+    }
+
+    // A yield whose control flow was dropped sat somewhere other than statement position.
+    fn check_yield(&mut self, control_flow: &ControlFlow) {
+        if self.yielded.is_some() && *control_flow != ControlFlow::Yield {
+            self.yielded = None;
+            runtime_error!("yield can only be used as a statement inside a generator");
+        }
+    }
+
+    fn handle_sqrt(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        if let Value::Complex(val) = value {
+            return Value::Complex(val.sqrt());
+        }
+
+        if let Value::Decimal(val) = &value {
+            if !val.is_negative() {
+                return Value::Decimal(self.decimal_sqrt(val));
+            }
+        }
+
+        let val = self.to_float(&value);
+        if val >= 0.0 {
+            Value::Float(val.sqrt())
+        } else if self.complex_mode {
+            Value::Complex(Complex::new(0.0, (-val).sqrt()))
+        } else {
+            runtime_error!("Cannot take the square root of a negative number");
+        }
+    }
+
+    fn handle_abs(&mut self, root: &Node) -> Value {
+        match self.evaluate_helper(&root.children[0], &mut ControlFlow::None) {
+            Value::Number(val) => match val.checked_abs() {
+                Some(val) => Value::Number(val),
+                None => self.normalize_big_number(BigInt::from(val).abs()),
+            },
+            Value::BigNumber(val) => Value::BigNumber(val.abs()),
+            Value::Float(val) => Value::Float(val.abs()),
+            Value::Rational(val) => Value::Rational(val.abs()),
+            Value::Decimal(val) => Value::Decimal(val.abs()),
+            Value::Complex(val) => Value::Float(val.norm()),
+            Value::Quantity(val) => Value::Quantity(Quantity {
+                value: val.value.abs(),
+                unit: val.unit,
+            }),
+            _ => runtime_error!("Expected a number"),
+        }
+    }
+
+    fn handle_to(&mut self, root: &Node) -> Value {
+        let quantity = self.evaluate_helper(&root.children[0].children[0], &mut ControlFlow::None);
+        let target = self.evaluate_helper(&root.children[0].children[1], &mut ControlFlow::None);
+
+        match (quantity, target) {
+            (Value::Quantity(quantity), Value::Quantity(target)) => {
+                if quantity.unit.dimension != target.unit.dimension {
+                    runtime_error!(
+                        "Cannot convert {} to {}",
+                        quantity.unit.name,
+                        target.unit.name
+                    );
+                }
+                Value::Quantity(Quantity {
+                    value: quantity.value,
+                    unit: target.unit,
+                })
+            }
+            _ => runtime_error!("Expected a quantity"),
+        }
+    }
+
+    fn handle_radix_format(&mut self, root: &Node, radix: u32, prefix: &str) -> Value {
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        let value = self
+            .to_integer(&value)
+            .unwrap_or_else(|| runtime_error!("Expected an integer"));
+        let sign = if value.is_negative() { "-" } else { "" };
+        Value::String(format!(
+            "{}{}{}",
+            sign,
+            prefix,
+            value.abs().to_str_radix(radix)
+        ))
+    }
+
     fn handle_input(&mut self) -> Value {
         let mut input = String::new();
         std::io::stdin()
@@ -489,6 +1242,10 @@ impl Interpreter {
     }
 
     fn handle_assignment(&mut self, root: &Node) -> Value {
+        if root.children[0].node_type == NodeType::Pattern {
+            return self.handle_destructuring(root);
+        }
+
         let type_annotation = root.children[0]
             .value
             .as_ref()
@@ -497,88 +1254,205 @@ impl Interpreter {
             .value
             .as_ref()
             .expect("expected an identifier");
-        let value = self.evaluate_helper(&root.children[2], &mut false);
+        let mut value = self.evaluate_helper(&root.children[2], &mut ControlFlow::None);
 
         if self.scope_manager.contains_identifier(identifier) {
-            panic!("Identifier {} already exists", identifier);
+            runtime_error!("Identifier {} already exists", identifier);
         }
 
         if let Value::Boolean(_) = value {
             if type_annotation != "bool" {
-                panic!("Type mismatch");
+                runtime_error!("Type mismatch");
             }
-        } else if let Value::Number(_) = value {
-            if type_annotation != "int" {
-                panic!("Type mismatch");
+        } else if let Value::Number(_)
+        | Value::BigNumber(_)
+        | Value::Float(_)
+        | Value::Rational(_)
+        | Value::Decimal(_)
+        | Value::Complex(_) = value
+        {
+            value = self.coerce_number(value, type_annotation);
+        } else if let Value::Quantity(_) = value {
+            if type_annotation != "quantity" {
+                runtime_error!("Type mismatch");
             }
         } else if let Value::String(_) = value {
             if type_annotation != "str" {
-                panic!("Type mismatch");
+                runtime_error!("Type mismatch");
             }
         } else if let Value::List(_) = value {
             if type_annotation != "list" {
-                panic!("Type mismatch");
+                runtime_error!("Type mismatch");
+            }
+        } else if let Value::Range { .. } = value {
+            if type_annotation == "list" {
+                value = range_to_list(&value);
+            } else if type_annotation != "range" {
+                runtime_error!("Type mismatch");
             }
         } else if let Value::Function(_) = value {
             if type_annotation != "function" {
-                panic!("Type mismatch");
+                runtime_error!("Type mismatch");
+            }
+        } else if let Value::Generator(_) = value {
+            if type_annotation != "generator" {
+                runtime_error!("Type mismatch");
             }
         } else if let Value::Map(_) = value {
             if type_annotation != "map" {
-                panic!("Type mismatch");
+                runtime_error!("Type mismatch");
+            }
+        } else if let Value::Struct { name, .. } | Value::Enum { name, .. } = &value {
+            if type_annotation != name {
+                runtime_error!("Type mismatch");
             }
         } else {
-            panic!("Invalid type");
+            runtime_error!("Invalid type");
         }
         self.scope_manager
             .insert_identifier(identifier.clone(), value.clone());
         return value;
     }
 
+    fn handle_destructuring(&mut self, root: &Node) -> Value {
+        let value = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
+
+        let mut bindings = HashMap::new();
+        if !self.match_pattern(&root.children[0], &value, &mut bindings) {
+            runtime_error!(
+                "Cannot destructure {}: value does not match the pattern",
+                self.format_value(&value)
+            );
+        }
+
+        for (identifier, value) in bindings {
+            if self.scope_manager.contains_identifier(&identifier) {
+                runtime_error!("Identifier {} already exists", identifier);
+            }
+            self.scope_manager.insert_identifier(identifier, value);
+        }
+        return value;
+    }
+
     fn handle_reassignment(&mut self, root: &Node) -> Value {
+        if root.children[0].node_type != NodeType::Identifier {
+            return self.handle_target_assignment(root);
+        }
+
         let identifier = root.children[0]
             .value
             .as_ref()
             .expect("expected an identifier");
-        let value = self.evaluate_helper(&root.children[1], &mut false);
+        let value = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
 
         self.scope_manager
             .reassign_identifier(identifier.clone(), value.clone());
         return value;
     }
 
-    fn handle_declaration(&mut self, root: &Node) -> Value {
-        let identifier = root.children[0]
-            .value
-            .as_ref()
-            .expect("expected an identifier");
+    fn handle_target_assignment(&mut self, root: &Node) -> Value {
+        let mut path = Vec::new();
+        let mut target = &root.children[0];
+        while let NodeType::Index | NodeType::MapIndex = target.node_type {
+            path.push(self.evaluate_helper(&target.children[1], &mut ControlFlow::None));
+            target = &target.children[0];
+        }
+        path.reverse();
 
-        self.scope_manager.insert_identifier(
-            identifier.clone(),
-            Value::Function(root.children[1].clone()),
-        );
+        if target.node_type != NodeType::Identifier {
+            runtime_error!("Invalid assignment target");
+        }
+        let identifier = target.value.as_ref().expect("expected an identifier");
+
+        let value = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
+        let container = self.scope_manager.get_identifier(identifier);
+        let container = self.assign_path(container, &path, value.clone());
 
-        return Value::Function(root.children[1].clone());
+        self.scope_manager
+            .reassign_identifier(identifier.clone(), container);
+        return value;
     }
 
-    fn handle_block(&mut self, root: &Node, early_return: &mut bool) -> Value {
-        for child in root.children.iter().take(root.children.len() - 1) {
-            let result = self.evaluate_helper(child, early_return);
-            if *early_return {
+    fn assign_path(&self, container: Value, path: &[Value], value: Value) -> Value {
+        let Some((key, rest)) = path.split_first() else {
+            return check_reassignment(&container, value);
+        };
+
+        match (container, key) {
+            (Value::List(mut list), key) => {
+                let Some(index) = self.to_index(key) else {
+                    runtime_error!("Expected a number");
+                };
+                if index < 0 || index as usize >= list.len() {
+                    runtime_error!("Index out of range");
+                }
+                let element = list[index as usize].clone();
+                list[index as usize] = self.assign_path(element, rest, value);
+                Value::List(list)
+            }
+            (Value::Map(mut map), Value::String(key)) => {
+                let field = match map.get(key) {
+                    Some(field) => self.assign_path(field.clone(), rest, value),
+                    None if rest.is_empty() => value,
+                    None => runtime_error!("Key {} not found", key),
+                };
+                map.insert(key.clone(), field);
+                Value::Map(map)
+            }
+            (Value::Struct { name, mut fields }, Value::String(key)) => {
+                let Some(field) = fields.iter_mut().find(|(field, _)| field == key) else {
+                    runtime_error!("Unknown field {} for struct {}", key, name);
+                };
+                field.1 = self.assign_path(field.1.clone(), rest, value);
+                Value::Struct { name, fields }
+            }
+            (Value::Map(_), _) => runtime_error!("Expected a string"),
+            _ => runtime_error!("Expected a list or a map"),
+        }
+    }
+
+    fn handle_declaration(&mut self, root: &Node) -> Value {
+        let identifier = root.children[0]
+            .value
+            .as_ref()
+            .expect("expected an identifier");
+
+        let function = Value::Function(Closure {
+            function: root.children[1].clone(),
+            environment: self.scope_manager.capture(),
+        });
+        self.scope_manager
+            .insert_identifier(identifier.clone(), function.clone());
+
+        return function;
+    }
+
+    fn handle_block(&mut self, root: &Node, control_flow: &mut ControlFlow) -> Value {
+        let start = match self.resume_frame() {
+            Some(Frame::Block(index)) => index,
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => 0,
+        };
+
+        let mut result = Value::Null;
+        for (index, child) in root.children.iter().enumerate().skip(start) {
+            result = self.evaluate_helper(child, control_flow);
+            self.check_yield(control_flow);
+            if *control_flow == ControlFlow::Yield {
+                self.frames.push(Frame::Block(index));
+            }
+            if *control_flow != ControlFlow::None {
                 return result;
             }
         }
-        return self.evaluate_helper(
-            root.children.last().expect("expected a child"),
-            early_return,
-        );
+        return result;
     }
 
     fn handle_list(&mut self, root: &Node) -> Value {
         let values: Vec<Value> = root
             .children
             .iter()
-            .map(|child| self.evaluate_helper(child, &mut false))
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
             .collect();
 
         return Value::List(values);
@@ -590,14 +1464,14 @@ impl Interpreter {
         let values: Vec<Value> = root
             .children
             .iter()
-            .map(|child| self.evaluate_helper(child, &mut false))
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
             .collect();
 
         for i in (0..values.len()).step_by(2) {
             let key = if let Value::String(key) = &values[i] {
                 key.clone()
             } else {
-                panic!("Expected a string");
+                runtime_error!("Expected a string");
             };
             map.insert(key, values[i + 1].clone());
         }
@@ -606,118 +1480,514 @@ impl Interpreter {
     }
 
     fn handle_map_index(&mut self, root: &Node) -> Value {
-        let map = self.evaluate_helper(&root.children[0], &mut false);
-        let key = self.evaluate_helper(&root.children[1], &mut false);
+        let map = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+        let key = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
 
         let key = if let Value::String(key) = key {
             key
         } else {
-            panic!("Expected a string");
+            runtime_error!("Expected a string");
         };
 
         if let Value::Map(map) = map {
-            return map.get(&key.to_string()).unwrap().clone();
+            return map
+                .get(&key)
+                .unwrap_or_else(|| runtime_error!("Key {} not found", key))
+                .clone();
+        } else if let Value::Struct { name, fields } = map {
+            return fields
+                .into_iter()
+                .find(|(field, _)| *field == key)
+                .map(|(_, value)| value)
+                .unwrap_or_else(|| runtime_error!("Unknown field {} for struct {}", key, name));
         } else {
-            panic!("Expected a map");
+            runtime_error!("Expected a map");
         }
     }
 
     fn handle_index(&mut self, root: &Node) -> Value {
-        let indexable = self.evaluate_helper(&root.children[0], &mut false);
+        let indexable = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
 
-        let index = self.evaluate_helper(&root.children[1], &mut false);
-        let index = if let Value::Number(index) = index {
-            index
-        } else {
-            panic!("Expected a number");
+        let index = self.evaluate_helper(&root.children[1], &mut ControlFlow::None);
+        if let Value::Map(map) = indexable {
+            let key = if let Value::String(key) = index {
+                key
+            } else {
+                runtime_error!("Expected a string");
+            };
+            return map
+                .get(&key)
+                .unwrap_or_else(|| runtime_error!("Key {} not found", key))
+                .clone();
+        }
+
+        let Some(index) = self.to_index(&index) else {
+            runtime_error!("Expected a number");
         };
 
         if let Value::String(string) = indexable {
+            if index < 0 || index as usize >= string.len() {
+                runtime_error!("Index out of range");
+            }
             return Value::String((string.as_bytes()[index as usize].clone() as char).to_string());
         }
+        if let Value::Range { start, .. } = indexable {
+            if index < 0 || !range_bounds(&indexable).contains(&(start as i64 + index as i64)) {
+                runtime_error!("Index out of range");
+            }
+            return Value::Number(start + index);
+        }
         if let Value::List(list) = indexable {
+            if index < 0 || index as usize >= list.len() {
+                runtime_error!("Index out of range");
+            }
             return list[index as usize].clone();
         } else {
-            panic!("Expected a string or a list");
+            runtime_error!("Expected a string, list, range or map");
         }
     }
 
-    fn handle_while(&mut self, root: &Node, early_return: &mut bool) -> Value {
-        let condition_val = self.evaluate_helper(&root.children[0], &mut false);
-        let condition = match condition_val {
-            Value::Boolean(val) => val,
-            _ => panic!("Expected a boolean"),
+    fn handle_while(
+        &mut self,
+        root: &Node,
+        label: Option<&String>,
+        control_flow: &mut ControlFlow,
+    ) -> Value {
+        let mut resumed = match self.resume_frame() {
+            Some(Frame::Loop) => true,
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => false,
         };
 
         let mut result = Value::Null;
         let block = &root.children[1];
-        if condition {
-            loop {
-                for child in block.children.iter() {
-                    result = self.evaluate_helper(child, early_return);
-                    if *early_return {
-                        return result;
-                    }
-                }
-
-                let condition_val = self.evaluate_helper(&root.children[0], &mut false);
+        loop {
+            if !resumed {
+                let condition_val = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
                 let condition = match condition_val {
                     Value::Boolean(val) => val,
-                    _ => panic!("Expected a boolean"),
+                    _ => runtime_error!("Expected a boolean"),
                 };
 
                 if !condition {
                     break;
                 }
             }
+            resumed = false;
+
+            match self.handle_loop_body(block, label, control_flow, &mut result) {
+                LoopStep::Next => {}
+                LoopStep::Break => break,
+                LoopStep::Exit => {
+                    if *control_flow == ControlFlow::Yield {
+                        self.frames.push(Frame::Loop);
+                    }
+                    return result;
+                }
+            }
         }
 
         return result;
     }
 
-    fn handle_if(&mut self, root: &Node, early_return: &mut bool) -> Value {
-        let condition = self.evaluate_helper(&root.children[0], &mut false);
-        let condition = match condition {
-            Value::Boolean(val) => val,
-            _ => panic!("Expected a boolean"),
+    fn handle_for(
+        &mut self,
+        root: &Node,
+        label: Option<&String>,
+        control_flow: &mut ControlFlow,
+    ) -> Value {
+        let variables: Vec<String> = root
+            .children
+            .iter()
+            .take(root.children.len() - 2)
+            .map(|child| child.value.clone().expect("expected a loop variable"))
+            .collect();
+        let block = root.children.last().expect("expected a block");
+
+        let (mut source, mut resumed) = match self.resume_frame() {
+            Some(Frame::For(source)) => (source, true),
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => {
+                let iterable = self.evaluate_helper(
+                    &root.children[root.children.len() - 2],
+                    &mut ControlFlow::None,
+                );
+                (self.loop_source(iterable), false)
+            }
         };
 
-        if condition {
-            if root.children.len() >= 2 {
-                let result = self.evaluate_helper(&root.children[1], early_return);
+        let mut result = Value::Null;
+        loop {
+            // A resumed loop continues the iteration whose scope is already in place.
+            if !resumed {
+                let item = match &mut source {
+                    LoopSource::Items(items) => items.next(),
+                    LoopSource::Generator(generator) => {
+                        self.resume_generator(generator).map(|value| vec![value])
+                    }
+                };
+                let Some(item) = item else {
+                    break;
+                };
+                if variables.len() > item.len() {
+                    runtime_error!("Too many loop variables");
+                }
+
+                self.scope_manager.new_scope();
+                for (variable, value) in variables.iter().zip(item) {
+                    self.scope_manager
+                        .insert_identifier(variable.clone(), value);
+                }
+            }
+            resumed = false;
+
+            let step = self.handle_loop_body(block, label, control_flow, &mut result);
+            if *control_flow == ControlFlow::Yield {
+                self.frames.push(Frame::For(source));
                 return result;
-            } else {
-                return Value::Null;
             }
+            self.scope_manager.pop_scope();
+            match step {
+                LoopStep::Next => {}
+                LoopStep::Break => break,
+                LoopStep::Exit => return result,
+            }
+        }
+
+        return result;
+    }
+
+    fn loop_source(&self, iterable: Value) -> LoopSource {
+        match iterable {
+            Value::List(list) => {
+                LoopSource::Items(Box::new(list.into_iter().map(|item| vec![item])))
+            }
+            Value::Range { .. } => LoopSource::Items(Box::new(
+                range_bounds(&iterable).map(|i| vec![Value::Number(i as i32)]),
+            )),
+            Value::String(string) => LoopSource::Items(Box::new(
+                string
+                    .chars()
+                    .map(|c| vec![Value::String(c.to_string())])
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            )),
+            Value::Map(map) => {
+                let mut keys: Vec<String> = map.keys().cloned().collect();
+                keys.sort();
+                LoopSource::Items(Box::new(keys.into_iter().map(move |key| {
+                    let value = map[&key].clone();
+                    vec![Value::String(key), value]
+                })))
+            }
+            Value::Generator(generator) => LoopSource::Generator(generator),
+            _ => runtime_error!("Expected a list, range, string, map or generator"),
+        }
+    }
+
+    fn handle_loop_body(
+        &mut self,
+        block: &Node,
+        label: Option<&String>,
+        control_flow: &mut ControlFlow,
+        result: &mut Value,
+    ) -> LoopStep {
+        let start = match self.resume_frame() {
+            Some(Frame::Block(index)) => index,
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => 0,
+        };
+
+        for (index, child) in block.children.iter().enumerate().skip(start) {
+            *result = self.evaluate_helper(child, control_flow);
+            self.check_yield(control_flow);
+            match control_flow {
+                ControlFlow::None => {}
+                ControlFlow::Return => return LoopStep::Exit,
+                ControlFlow::Yield => {
+                    self.frames.push(Frame::Block(index));
+                    return LoopStep::Exit;
+                }
+                ControlFlow::Break(target) | ControlFlow::Continue(target)
+                    if target.is_some() && target.as_ref() != label =>
+                {
+                    return LoopStep::Exit;
+                }
+                ControlFlow::Break(_) => {
+                    *control_flow = ControlFlow::None;
+                    return LoopStep::Break;
+                }
+                ControlFlow::Continue(_) => {
+                    *control_flow = ControlFlow::None;
+                    return LoopStep::Next;
+                }
+            }
+        }
+        return LoopStep::Next;
+    }
+
+    fn handle_match(&mut self, root: &Node, control_flow: &mut ControlFlow) -> Value {
+        match self.resume_frame() {
+            Some(Frame::Branch(index)) => {
+                return self.handle_arm_body(root, index, control_flow);
+            }
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => {}
+        }
+
+        let value = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+
+        for (index, arm) in root.children.iter().enumerate().skip(1) {
+            let mut bindings = HashMap::new();
+            if !self.match_pattern(&arm.children[0], &value, &mut bindings) {
+                continue;
+            }
+
+            self.scope_manager.new_scope_with_values(bindings);
+            let guard = match self.evaluate_helper(&arm.children[1], &mut ControlFlow::None) {
+                Value::Boolean(val) => val,
+                _ => runtime_error!("Expected a boolean"),
+            };
+            if !guard {
+                self.scope_manager.pop_scope();
+                continue;
+            }
+
+            return self.handle_arm_body(root, index, control_flow);
+        }
+
+        runtime_error!("No match arm for value {}", self.format_value(&value));
+    }
+
+    fn handle_arm_body(
+        &mut self,
+        root: &Node,
+        index: usize,
+        control_flow: &mut ControlFlow,
+    ) -> Value {
+        let result = self.evaluate_helper(&root.children[index].children[2], control_flow);
+        if *control_flow == ControlFlow::Yield {
+            self.frames.push(Frame::Branch(index));
         } else {
-            if root.children.len() >= 3 {
-                return self.evaluate_helper(&root.children[2], &mut false);
-            } else {
-                return Value::Null;
+            self.scope_manager.pop_scope();
+        }
+        return result;
+    }
+
+    fn match_pattern(
+        &self,
+        pattern: &Node,
+        value: &Value,
+        bindings: &mut HashMap<String, Value>,
+    ) -> bool {
+        let identifier = |node: &Node| node.value.clone().expect("expected an identifier");
+
+        match pattern.value.as_deref().expect("expected a pattern kind") {
+            "wildcard" => true,
+            "binding" => {
+                bindings.insert(identifier(&pattern.children[0]), value.clone());
+                true
+            }
+            "typed" => {
+                let annotation = pattern.children[0]
+                    .value
+                    .as_ref()
+                    .expect("expected a type annotation");
+                if !self.matches_type(annotation, value) {
+                    return false;
+                }
+                let name = identifier(&pattern.children[1]);
+                if name != "_" {
+                    bindings.insert(name, value.clone());
+                }
+                true
+            }
+            "literal" => {
+                let literal = self.parse_value(&pattern.children[0]);
+                self.literal_matches(&literal, value)
+            }
+            "list" => {
+                let Value::List(list) = value else {
+                    return false;
+                };
+                let (patterns, rest) = match pattern.children.split_last() {
+                    Some((last, patterns)) if last.value.as_deref() == Some("rest") => {
+                        (patterns, Some(last))
+                    }
+                    _ => (pattern.children.as_slice(), None),
+                };
+
+                if list.len() < patterns.len() || (rest.is_none() && list.len() != patterns.len()) {
+                    return false;
+                }
+                for (pattern, item) in patterns.iter().zip(list) {
+                    if !self.match_pattern(pattern, item, bindings) {
+                        return false;
+                    }
+                }
+                if let Some(rest) = rest {
+                    bindings.insert(
+                        identifier(&rest.children[0]),
+                        Value::List(list[patterns.len()..].to_vec()),
+                    );
+                }
+                true
+            }
+            "variant" => {
+                let Value::Enum {
+                    name,
+                    variant,
+                    payload,
+                } = value
+                else {
+                    return false;
+                };
+                if Some(name) != pattern.children[0].value.as_ref()
+                    || Some(variant) != pattern.children[1].value.as_ref()
+                {
+                    return false;
+                }
+                let patterns = &pattern.children[2..];
+                if patterns.len() != payload.len() {
+                    runtime_error!(
+                        "Variant {}.{} has {} values, pattern expects {}",
+                        name,
+                        variant,
+                        payload.len(),
+                        patterns.len()
+                    );
+                }
+                patterns
+                    .iter()
+                    .zip(payload)
+                    .all(|(pattern, item)| self.match_pattern(pattern, item, bindings))
+            }
+            "map" => {
+                let lookup = |key: &str| match value {
+                    Value::Map(map) => map.get(key),
+                    Value::Struct { fields, .. } => fields
+                        .iter()
+                        .find(|(field, _)| field == key)
+                        .map(|(_, value)| value),
+                    _ => None,
+                };
+                if !matches!(value, Value::Map(_) | Value::Struct { .. }) {
+                    return false;
+                }
+                for pair in pattern.children.chunks(2) {
+                    let Value::String(key) = self.parse_value(&pair[0]) else {
+                        runtime_error!("Expected a string");
+                    };
+                    match lookup(&key) {
+                        Some(field) if self.match_pattern(&pair[1], field, bindings) => {}
+                        _ => return false,
+                    }
+                }
+                true
+            }
+            _ => runtime_error!("Invalid pattern"),
+        }
+    }
+
+    fn matches_type(&self, annotation: &str, value: &Value) -> bool {
+        if let Value::Struct { name, .. } | Value::Enum { name, .. } = value {
+            return name == annotation;
+        }
+        matches!(
+            (annotation, value),
+            ("bool", Value::Boolean(_))
+                | ("int", Value::Number(_) | Value::BigNumber(_))
+                | ("float", Value::Float(_))
+                | ("rational", Value::Rational(_))
+                | ("decimal", Value::Decimal(_))
+                | ("complex", Value::Complex(_))
+                | ("quantity", Value::Quantity(_))
+                | ("str", Value::String(_))
+                | ("list", Value::List(_))
+                | ("range", Value::Range { .. })
+                | ("function", Value::Function(_))
+                | ("generator", Value::Generator(_))
+                | ("map", Value::Map(_))
+        )
+    }
+
+    fn literal_matches(&self, literal: &Value, value: &Value) -> bool {
+        match (literal, value) {
+            (Value::String(first), Value::String(second)) => first == second,
+            (Value::Boolean(first), Value::Boolean(second)) => first == second,
+            (Value::String(_) | Value::Boolean(_), _) => false,
+            (
+                _,
+                Value::Number(_)
+                | Value::BigNumber(_)
+                | Value::Float(_)
+                | Value::Rational(_)
+                | Value::Decimal(_)
+                | Value::Complex(_),
+            ) => self.values_equal(literal, value),
+            _ => false,
+        }
+    }
+
+    fn handle_if(&mut self, root: &Node, control_flow: &mut ControlFlow) -> Value {
+        let branch = match self.resume_frame() {
+            Some(Frame::Branch(index)) => index,
+            Some(_) => runtime_error!("Invalid resume state"),
+            None => {
+                let condition = self.evaluate_helper(&root.children[0], &mut ControlFlow::None);
+                match condition {
+                    Value::Boolean(true) => 1,
+                    Value::Boolean(false) => 2,
+                    _ => runtime_error!("Expected a boolean"),
+                }
             }
+        };
+
+        if branch >= root.children.len() {
+            return Value::Null;
+        }
+        let result = self.evaluate_helper(&root.children[branch], control_flow);
+        if *control_flow == ControlFlow::Yield {
+            self.frames.push(Frame::Branch(branch));
+        }
+        return result;
+    }
+
+    fn handle_logical_operator(&mut self, root: &Node) -> Value {
+        let first = match self.evaluate_helper(&root.children[0], &mut ControlFlow::None) {
+            Value::Boolean(val) => val,
+            _ => runtime_error!("Expected a boolean"),
+        };
+
+        let operator = root.value.as_ref().unwrap().as_str();
+        if (operator == "&&" && !first) || (operator == "||" && first) {
+            return Value::Boolean(first);
+        }
+
+        match self.evaluate_helper(&root.children[1], &mut ControlFlow::None) {
+            Value::Boolean(second) => Value::Boolean(second),
+            _ => runtime_error!("Expected a boolean"),
         }
     }
 
     fn handle_operator(&mut self, root: &Node) -> Value {
+        if let "&&" | "||" = root.value.as_ref().unwrap().as_str() {
+            return self.handle_logical_operator(root);
+        }
+
         let values: Vec<Value> = root
             .children
             .iter()
-            .map(|child| self.evaluate_helper(child, &mut false))
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
             .collect();
 
         match root.value.as_ref().unwrap().as_str() {
             "+" => {
-                if let Value::Number(first) = &values[0] {
-                    if let Value::Number(second) = &values[1] {
-                        return Value::Number(first + second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else if let Value::String(first) = &values[0] {
+                if let Value::String(first) = &values[0] {
                     if let Value::String(second) = &values[1] {
                         return Value::String(format!("{}{}", first, second));
                     } else {
-                        panic!("Expected a string");
+                        runtime_error!("Expected a string");
                     }
                 } else if let Value::List(first) = &values[0] {
                     if let Value::List(second) = &values[1] {
@@ -725,150 +1995,568 @@ impl Interpreter {
                         result.extend(second.clone());
                         return Value::List(result);
                     } else {
-                        panic!("Expected a list");
+                        runtime_error!("Expected a list");
                     }
                 } else {
-                    panic!("Expected a number");
+                    return self.handle_arithmetic("+", &values[0], &values[1]);
                 }
             }
             "-" => {
-                if let Value::Number(first) = values[0] {
-                    if values.len() == 1 {
-                        return Value::Number(-first);
-                    }
-                    if let Value::Number(second) = values[1] {
-                        return Value::Number(first - second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else {
-                    panic!("Expected a number");
+                if values.len() == 1 {
+                    return match &values[0] {
+                        Value::Number(first) => match first.checked_neg() {
+                            Some(result) => Value::Number(result),
+                            None => self.normalize_big_number(-BigInt::from(*first)),
+                        },
+                        Value::BigNumber(first) => self.normalize_big_number(-first),
+                        Value::Float(first) => Value::Float(-first),
+                        Value::Rational(first) => Value::Rational(-first),
+                        Value::Decimal(first) => Value::Decimal(-first),
+                        Value::Complex(first) => Value::Complex(-first),
+                        Value::Quantity(first) => Value::Quantity(Quantity {
+                            value: -first.value,
+                            unit: first.unit.clone(),
+                        }),
+                        _ => runtime_error!("Expected a number"),
+                    };
                 }
+                return self.handle_arithmetic("-", &values[0], &values[1]);
             }
-            "*" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Number(first * second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else {
-                    panic!("Expected a number");
-                }
+            "**" => {
+                return self.handle_power(&values[0], &values[1]);
             }
-            "/" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Number(first / second);
-                    } else {
-                        panic!("Expected a number");
-                    }
+            ".." | "..=" => {
+                if let (Some(start), Some(end)) =
+                    (self.to_index(&values[0]), self.to_index(&values[1]))
+                {
+                    return Value::Range {
+                        start,
+                        end,
+                        inclusive: root.value.as_ref().unwrap() == "..=",
+                    };
                 } else {
-                    panic!("Expected a number");
+                    runtime_error!("Range bounds must be integers");
                 }
             }
-            "%" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Number(first % second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else {
-                    panic!("Expected a number");
-                }
+            "&" | "|" | "^" | "<<" | ">>" => {
+                return self.handle_bitwise(root.value.as_ref().unwrap(), &values[0], &values[1]);
+            }
+            "~" => {
+                let value = self
+                    .to_integer(&values[0])
+                    .unwrap_or_else(|| runtime_error!("Expected an integer"));
+                return self.normalize_big_number(!value);
+            }
+            "*" | "/" | "%" => {
+                return self.handle_arithmetic(
+                    root.value.as_ref().unwrap(),
+                    &values[0],
+                    &values[1],
+                );
             }
             "==" => {
-                if let Value::Number(first) = &values[0] {
-                    if let Value::Number(second) = &values[1] {
-                        return Value::Boolean(first == second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else if let Value::String(first) = &values[0] {
-                    if let Value::String(second) = &values[1] {
-                        return Value::Boolean(first == second);
-                    } else {
-                        panic!("Expected a string");
-                    }
-                } else if let Value::Boolean(first) = &values[0] {
-                    if let Value::Boolean(second) = &values[1] {
-                        return Value::Boolean(first == second);
-                    } else {
-                        panic!("Expected a boolean");
-                    }
-                } else {
-                    panic!("Expected a number");
-                }
+                return Value::Boolean(self.values_equal(&values[0], &values[1]));
             }
             "!=" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Boolean(first != second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else if let Value::String(first) = &values[0] {
-                    if let Value::String(second) = &values[1] {
-                        return Value::Boolean(first != second);
-                    } else {
-                        panic!("Expected a string");
-                    }
-                } else if let Value::Boolean(first) = &values[0] {
-                    if let Value::Boolean(second) = &values[1] {
-                        return Value::Boolean(first != second);
-                    } else {
-                        panic!("Expected a boolean");
-                    }
-                } else {
-                    panic!("Expected a number");
-                }
+                return Value::Boolean(!self.values_equal(&values[0], &values[1]));
             }
             ">" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Boolean(first > second);
-                    } else {
-                        panic!("Expected a number");
-                    }
-                } else {
-                    panic!("Expected a number");
-                }
+                let ordering = self.compare_numbers(&values[0], &values[1]);
+                return Value::Boolean(ordering == Some(Ordering::Greater));
             }
             ">=" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Boolean(first >= second);
-                    } else {
-                        panic!("Expected a number");
-                    }
+                let ordering = self.compare_numbers(&values[0], &values[1]);
+                return Value::Boolean(matches!(
+                    ordering,
+                    Some(Ordering::Greater | Ordering::Equal)
+                ));
+            }
+            "<" => {
+                let ordering = self.compare_numbers(&values[0], &values[1]);
+                return Value::Boolean(ordering == Some(Ordering::Less));
+            }
+            "<=" => {
+                let ordering = self.compare_numbers(&values[0], &values[1]);
+                return Value::Boolean(matches!(ordering, Some(Ordering::Less | Ordering::Equal)));
+            }
+            "!" => {
+                if let Value::Boolean(first) = values[0] {
+                    return Value::Boolean(!first);
                 } else {
-                    panic!("Expected a number");
+                    runtime_error!("Expected a boolean");
                 }
             }
-            "<" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Boolean(first < second);
-                    } else {
-                        panic!("Expected a number");
+            _ => runtime_error!("Invalid operator"),
+        }
+    }
+
+    fn handle_arithmetic(&self, operator: &str, first: &Value, second: &Value) -> Value {
+        if let (Value::Number(first), Value::Number(second)) = (first, second) {
+            let result = match operator {
+                "+" => first.checked_add(*second),
+                "-" => first.checked_sub(*second),
+                "*" => first.checked_mul(*second),
+                "/" => first.checked_div(*second),
+                "%" => first.checked_rem(*second),
+                _ => runtime_error!("Invalid operator"),
+            };
+            if let Some(result) = result {
+                return Value::Number(result);
+            }
+        }
+
+        if let (Value::Quantity(_), _) | (_, Value::Quantity(_)) = (first, second) {
+            return self.quantity_arithmetic(operator, first, second);
+        }
+
+        if let (Some(first), Some(second)) = (self.to_big_number(first), self.to_big_number(second))
+        {
+            if second.is_zero() && (operator == "/" || operator == "%") {
+                runtime_error!("Division by zero");
+            }
+            let result = match operator {
+                "+" => first + second,
+                "-" => first - second,
+                "*" => first * second,
+                "/" => first / second,
+                "%" => first % second,
+                _ => runtime_error!("Invalid operator"),
+            };
+            return self.normalize_big_number(result);
+        }
+
+        if let (Some(first_rational), Some(second_rational)) =
+            (self.to_rational(first), self.to_rational(second))
+        {
+            let result = self.rational_arithmetic(operator, first_rational, second_rational);
+            if let (Value::Decimal(_), _) | (_, Value::Decimal(_)) = (first, second) {
+                return Value::Decimal(self.round_decimal(result));
+            }
+            return Value::Rational(result);
+        }
+
+        if let (Value::Complex(_), _) | (_, Value::Complex(_)) = (first, second) {
+            let first = self.to_complex(first);
+            let second = self.to_complex(second);
+            return match operator {
+                "+" => Value::Complex(first + second),
+                "-" => Value::Complex(first - second),
+                "*" => Value::Complex(first * second),
+                "/" => Value::Complex(first / second),
+                _ => runtime_error!("Invalid operator for complex numbers"),
+            };
+        }
+
+        let first = self.to_float(first);
+        let second = self.to_float(second);
+        match operator {
+            "+" => Value::Float(first + second),
+            "-" => Value::Float(first - second),
+            "*" => Value::Float(first * second),
+            "/" => Value::Float(first / second),
+            "%" => Value::Float(first % second),
+            _ => runtime_error!("Invalid operator"),
+        }
+    }
+
+    fn to_big_number(&self, value: &Value) -> Option<BigInt> {
+        match value {
+            Value::Number(val) => Some(BigInt::from(*val)),
+            Value::BigNumber(val) => Some(val.clone()),
+            _ => None,
+        }
+    }
+
+    fn normalize_big_number(&self, value: BigInt) -> Value {
+        match value.to_i32() {
+            Some(val) => Value::Number(val),
+            None => Value::BigNumber(value),
+        }
+    }
+
+    fn rational_arithmetic(
+        &self,
+        operator: &str,
+        first: BigRational,
+        second: BigRational,
+    ) -> BigRational {
+        if second.is_zero() && (operator == "/" || operator == "%") {
+            runtime_error!("Division by zero");
+        }
+        match operator {
+            "+" => first + second,
+            "-" => first - second,
+            "*" => first * second,
+            "/" => first / second,
+            "%" => first % second,
+            _ => runtime_error!("Invalid operator"),
+        }
+    }
+
+    fn to_rational(&self, value: &Value) -> Option<BigRational> {
+        match value {
+            Value::Rational(val) | Value::Decimal(val) => Some(val.clone()),
+            _ => self.to_big_number(value).map(BigRational::from_integer),
+        }
+    }
+
+    fn to_float(&self, value: &Value) -> f32 {
+        match value {
+            Value::Number(val) => *val as f32,
+            Value::BigNumber(val) => val
+                .to_f32()
+                .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+            Value::Float(val) => *val,
+            Value::Rational(val) | Value::Decimal(val) => val
+                .to_f32()
+                .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+            _ => runtime_error!("Expected a number"),
+        }
+    }
+
+    fn handle_power(&self, base: &Value, exponent: &Value) -> Value {
+        if let Value::Quantity(base) = base {
+            let exponent = self.integer_exponent(exponent).unwrap_or_else(|| {
+                runtime_error!("Quantities can only be raised to integer powers")
+            });
+            let dimension = base.unit.dimension.map(|val| val * exponent as i8);
+            return self.derived_quantity(base.value.powi(exponent), dimension);
+        }
+
+        if let (Value::Complex(_), _) | (_, Value::Complex(_)) = (base, exponent) {
+            return Value::Complex(self.to_complex(base).powc(self.to_complex(exponent)));
+        }
+
+        if let Some(exponent) = self.integer_exponent(exponent) {
+            match base {
+                Value::Number(val) if exponent >= 0 => {
+                    return match val.checked_pow(exponent as u32) {
+                        Some(result) => Value::Number(result),
+                        None => self.normalize_big_number(BigInt::from(*val).pow(exponent as u32)),
+                    };
+                }
+                Value::BigNumber(val) if exponent >= 0 => {
+                    return self.normalize_big_number(val.pow(exponent as u32));
+                }
+                Value::Rational(val) | Value::Decimal(val) => {
+                    if val.is_zero() && exponent < 0 {
+                        runtime_error!("Division by zero");
+                    }
+                    let result = val.pow(exponent);
+                    if let Value::Decimal(_) = base {
+                        return Value::Decimal(self.round_decimal(result));
                     }
+                    return Value::Rational(result);
+                }
+                _ => {}
+            }
+        }
+
+        let base_value = self.to_float(base);
+        let exponent_value = self.to_float(exponent);
+        if base_value < 0.0 && exponent_value.fract() != 0.0 && self.complex_mode {
+            let base = Complex::new(base_value, 0.0);
+            // `powf` goes through polar form and leaves a rounding error in the real part.
+            if exponent_value == 0.5 {
+                return Value::Complex(base.sqrt());
+            }
+            return Value::Complex(base.powf(exponent_value));
+        }
+
+        if let (Value::Decimal(_), _) | (_, Value::Decimal(_)) = (base, exponent) {
+            return self.decimal_power(base, exponent);
+        }
+        Value::Float(base_value.powf(exponent_value))
+    }
+
+    // An f32 `powf` would only be right to about 7 digits, so decimal mode takes the
+    // exponents it can compute at full precision: integers and halves.
+    fn decimal_power(&self, base: &Value, exponent: &Value) -> Value {
+        let rational = |value: &Value| match value {
+            Value::Float(val) => BigRational::from_float(*val),
+            _ => self.to_rational(value),
+        };
+        let (Some(base), Some(exponent)) = (rational(base), rational(exponent)) else {
+            runtime_error!("Expected a finite number");
+        };
+
+        let doubled = exponent * BigRational::from_integer(BigInt::from(2));
+        let Some(power) = doubled
+            .is_integer()
+            .then(|| doubled.to_integer().to_i32())
+            .flatten()
+        else {
+            runtime_error!("Decimal powers need an integer or half-integer exponent");
+        };
+        if base.is_negative() {
+            runtime_error!("Cannot take the square root of a negative number");
+        }
+        if base.is_zero() && power < 0 {
+            runtime_error!("Division by zero");
+        }
+        Value::Decimal(self.decimal_sqrt(&base.pow(power)))
+    }
+
+    fn decimal_sqrt(&self, value: &BigRational) -> BigRational {
+        // One extra digit, truncated, still rounds to the right last digit.
+        let precision = self.precision.unwrap_or(DEFAULT_PRECISION) + 1;
+        let scale = BigInt::from(10).pow(precision);
+        let root = (value.numer() * &scale * &scale / value.denom()).sqrt();
+        self.round_decimal(BigRational::new(root, scale))
+    }
+
+    fn handle_bitwise(&self, operator: &str, first: &Value, second: &Value) -> Value {
+        let first = self
+            .to_integer(first)
+            .unwrap_or_else(|| runtime_error!("Expected an integer"));
+        let second = self
+            .to_integer(second)
+            .unwrap_or_else(|| runtime_error!("Expected an integer"));
+
+        let result = match operator {
+            "&" => first & second,
+            "|" => first | second,
+            "^" => first ^ second,
+            "<<" | ">>" => {
+                let shift = second
+                    .to_usize()
+                    .unwrap_or_else(|| runtime_error!("Invalid shift amount"));
+                if operator == "<<" {
+                    first << shift
                 } else {
-                    panic!("Expected a number");
+                    first >> shift
                 }
             }
-            "<=" => {
-                if let Value::Number(first) = values[0] {
-                    if let Value::Number(second) = values[1] {
-                        return Value::Boolean(first <= second);
-                    } else {
-                        panic!("Expected a number");
+            _ => runtime_error!("Invalid operator"),
+        };
+        self.normalize_big_number(result)
+    }
+
+    fn to_integer(&self, value: &Value) -> Option<BigInt> {
+        match value {
+            Value::Rational(val) | Value::Decimal(val) if val.is_integer() => {
+                Some(val.to_integer())
+            }
+            _ => self.to_big_number(value),
+        }
+    }
+
+    // Whole-number decimals count as integers so indexing and ranges work in decimal mode.
+    fn to_index(&self, value: &Value) -> Option<i32> {
+        self.to_integer(value)?.to_i32()
+    }
+
+    fn integer_exponent(&self, value: &Value) -> Option<i32> {
+        match value {
+            Value::Number(val) => Some(*val),
+            Value::BigNumber(val) => val.to_i32(),
+            Value::Rational(val) | Value::Decimal(val) if val.is_integer() => {
+                val.to_integer().to_i32()
+            }
+            _ => None,
+        }
+    }
+
+    fn quantity_arithmetic(&self, operator: &str, first: &Value, second: &Value) -> Value {
+        match (first, second) {
+            (Value::Quantity(first), Value::Quantity(second)) => match operator {
+                "+" | "-" => {
+                    if first.unit.dimension != second.unit.dimension {
+                        runtime_error!(
+                            "Incompatible units: {} and {}",
+                            first.unit.name,
+                            second.unit.name
+                        );
                     }
-                } else {
-                    panic!("Expected a number");
+                    let value = if operator == "+" {
+                        first.value + second.value
+                    } else {
+                        first.value - second.value
+                    };
+                    Value::Quantity(Quantity {
+                        value,
+                        unit: first.unit.clone(),
+                    })
+                }
+                "*" => self.derived_quantity(
+                    first.value * second.value,
+                    self.combine_dimensions(first.unit.dimension, second.unit.dimension, 1),
+                ),
+                "/" => self.derived_quantity(
+                    first.value / second.value,
+                    self.combine_dimensions(first.unit.dimension, second.unit.dimension, -1),
+                ),
+                _ => runtime_error!("Invalid operator for quantities"),
+            },
+            (Value::Quantity(first), second) => {
+                let second = self.to_float(second) as f64;
+                let value = match operator {
+                    "*" => first.value * second,
+                    "/" => first.value / second,
+                    _ => runtime_error!(
+                        "Cannot apply {} to {} and a plain number",
+                        operator,
+                        first.unit.name
+                    ),
+                };
+                Value::Quantity(Quantity {
+                    value,
+                    unit: first.unit.clone(),
+                })
+            }
+            (first, Value::Quantity(second)) => {
+                let first = self.to_float(first) as f64;
+                match operator {
+                    "*" => Value::Quantity(Quantity {
+                        value: first * second.value,
+                        unit: second.unit.clone(),
+                    }),
+                    "/" => self.derived_quantity(
+                        first / second.value,
+                        self.combine_dimensions([0; 7], second.unit.dimension, -1),
+                    ),
+                    _ => runtime_error!(
+                        "Cannot apply {} to a plain number and {}",
+                        operator,
+                        second.unit.name
+                    ),
                 }
             }
-            _ => panic!("Invalid operator"),
+            _ => runtime_error!("Expected a quantity"),
+        }
+    }
+
+    fn combine_dimensions(&self, first: Dimension, second: Dimension, sign: i8) -> Dimension {
+        let mut dimension = first;
+        for (total, exponent) in dimension.iter_mut().zip(second) {
+            *total += sign * exponent;
+        }
+        dimension
+    }
+
+    fn derived_quantity(&self, value: f64, dimension: Dimension) -> Value {
+        if dimension == [0; 7] {
+            return Value::Float(value as f32);
+        }
+        Value::Quantity(Quantity {
+            value,
+            unit: Unit::from_dimension(dimension),
+        })
+    }
+
+    fn to_complex(&self, value: &Value) -> Complex<f32> {
+        match value {
+            Value::Complex(val) => *val,
+            _ => Complex::new(self.to_float(value), 0.0),
+        }
+    }
+
+    fn compare_numbers(&self, first: &Value, second: &Value) -> Option<Ordering> {
+        if let (Value::Quantity(first), Value::Quantity(second)) = (first, second) {
+            if first.unit.dimension != second.unit.dimension {
+                runtime_error!(
+                    "Incompatible units: {} and {}",
+                    first.unit.name,
+                    second.unit.name
+                );
+            }
+            return first.value.partial_cmp(&second.value);
+        }
+        if let (Value::Complex(_), _) | (_, Value::Complex(_)) = (first, second) {
+            runtime_error!("Complex numbers cannot be ordered");
+        }
+        if let (Value::Number(first), Value::Number(second)) = (first, second) {
+            return Some(first.cmp(second));
+        }
+        if let (Some(first), Some(second)) = (self.to_big_number(first), self.to_big_number(second))
+        {
+            return Some(first.cmp(&second));
+        }
+        if let (Some(first), Some(second)) = (self.to_rational(first), self.to_rational(second)) {
+            return Some(first.cmp(&second));
+        }
+        self.to_float(first).partial_cmp(&self.to_float(second))
+    }
+
+    fn coerce_number(&self, value: Value, type_annotation: &str) -> Value {
+        match (type_annotation, &value) {
+            ("int", Value::Number(_) | Value::BigNumber(_)) => value,
+            ("int", Value::Decimal(val)) if val.is_integer() => {
+                self.normalize_big_number(val.to_integer())
+            }
+            ("float", _) => Value::Float(self.to_float(&value)),
+            ("rational", Value::Number(_) | Value::BigNumber(_) | Value::Rational(_)) => {
+                Value::Rational(self.to_rational(&value).unwrap())
+            }
+            ("decimal", Value::Float(val)) => Value::Decimal(
+                self.round_decimal(
+                    BigRational::from_float(*val)
+                        .unwrap_or_else(|| runtime_error!("Expected a finite number")),
+                ),
+            ),
+            (
+                "decimal",
+                Value::Number(_) | Value::BigNumber(_) | Value::Rational(_) | Value::Decimal(_),
+            ) => Value::Decimal(self.round_decimal(self.to_rational(&value).unwrap())),
+            ("complex", _) => Value::Complex(self.to_complex(&value)),
+            _ => runtime_error!("Type mismatch"),
+        }
+    }
+
+    fn values_equal(&self, first: &Value, second: &Value) -> bool {
+        match (first, second) {
+            (Value::String(first), Value::String(second)) => first == second,
+            (Value::Boolean(first), Value::Boolean(second)) => first == second,
+            (Value::Range { .. }, Value::Range { .. }) => {
+                range_bounds(first) == range_bounds(second)
+            }
+            (Value::List(first), Value::List(second)) => {
+                first.len() == second.len()
+                    && first
+                        .iter()
+                        .zip(second)
+                        .all(|(first, second)| self.values_equal(first, second))
+            }
+            (
+                Value::Enum {
+                    name,
+                    variant,
+                    payload,
+                },
+                Value::Enum {
+                    name: other_name,
+                    variant: other_variant,
+                    payload: other_payload,
+                },
+            ) => {
+                name == other_name
+                    && variant == other_variant
+                    && payload
+                        .iter()
+                        .zip(other_payload)
+                        .all(|(first, second)| self.values_equal(first, second))
+            }
+            (Value::Enum { .. }, _) | (_, Value::Enum { .. }) => false,
+            (
+                Value::Struct { name, fields },
+                Value::Struct {
+                    name: other_name,
+                    fields: other_fields,
+                },
+            ) => {
+                name == other_name
+                    && fields
+                        .iter()
+                        .zip(other_fields)
+                        .all(|((_, first), (_, second))| self.values_equal(first, second))
+            }
+            (Value::Struct { .. }, _) | (_, Value::Struct { .. }) => false,
+            (Value::String(_), _) => runtime_error!("Expected a string"),
+            (Value::Boolean(_), _) => runtime_error!("Expected a boolean"),
+            (Value::Complex(_), _) | (_, Value::Complex(_)) => {
+                self.to_complex(first) == self.to_complex(second)
+            }
+            _ => self.compare_numbers(first, second) == Some(Ordering::Equal),
         }
     }
 
@@ -876,24 +2564,299 @@ impl Interpreter {
         let values: Vec<Value> = root.children[0]
             .children
             .iter()
-            .map(|child| self.evaluate_helper(child, &mut false))
+            .map(|child| self.evaluate_helper(child, &mut ControlFlow::None))
             .collect();
 
         let function = self.scope_manager.get_identifier(val).clone();
         return self.evaluate_function(&function, values);
     }
 
+    fn handle_try(&mut self, root: &Node, control_flow: &mut ControlFlow) -> Value {
+        let catch = root
+            .children
+            .iter()
+            .find(|child| child.node_type == NodeType::Catch);
+        let finally = root
+            .children
+            .iter()
+            .find(|child| child.node_type == NodeType::Finally);
+
+        let mut result = self.guarded(|this| this.handle_block(&root.children[0], control_flow));
+
+        if let (Err(failure), Some(catch)) = (&result, catch) {
+            let mut values = HashMap::new();
+            if let Some(identifier) = &catch.value {
+                values.insert(identifier.clone(), failure.to_value());
+            }
+            result = self.guarded(|this| {
+                this.scope_manager.new_scope_with_values(values);
+                let value = this.handle_block(&catch.children[0], control_flow);
+                this.scope_manager.pop_scope();
+                value
+            });
+        }
+
+        if *control_flow == ControlFlow::Yield {
+            runtime_error!("yield is not supported inside try");
+        }
+
+        if let Some(finally) = finally {
+            self.handle_block(&finally.children[0], &mut ControlFlow::None);
+        }
+
+        match result {
+            Ok(value) => value,
+            Err(Failure::Thrown(value)) => self.raise(value),
+            Err(Failure::Error(message)) => runtime_error!("{}", message),
+        }
+    }
+
+    fn guarded(&mut self, body: impl FnOnce(&mut Interpreter) -> Value) -> Result<Value, Failure> {
+        let scopes = self.scope_manager.capture();
+        let importing = self.importing.len();
+
+        let payload = match std::panic::catch_unwind(AssertUnwindSafe(|| body(self))) {
+            Ok(value) => return Ok(value),
+            Err(payload) => payload,
+        };
+        let error = match payload.downcast::<RuntimeError>() {
+            Ok(error) => error,
+            Err(payload) => std::panic::resume_unwind(payload),
+        };
+
+        // Unwinding skips the scope bookkeeping of everything in between.
+        self.scope_manager.restore_environment(scopes);
+        self.importing.truncate(importing);
+        self.frames.clear();
+        self.resuming = false;
+        self.yielded = None;
+
+        match self.thrown.take() {
+            Some(value) => Err(Failure::Thrown(value)),
+            None => Err(Failure::Error(error.0)),
+        }
+    }
+
+    fn raise(&mut self, value: Value) -> ! {
+        let message = format!("Uncaught exception: {}", self.format_value(&value));
+        self.thrown = Some(value);
+        runtime_error!("{}", message);
+    }
+
+    fn handle_import(&mut self, root: &Node) -> Value {
+        let relative = root.value.as_ref().expect("expected a module path");
+        let namespace = root.children[0]
+            .value
+            .as_ref()
+            .expect("expected a namespace");
+
+        let base = match self.importing.last().and_then(|path| path.parent()) {
+            Some(directory) => directory.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        let path = base
+            .join(relative)
+            .canonicalize()
+            .unwrap_or_else(|_| runtime_error!("Module {} not found", relative));
+
+        if let Some(start) = self.importing.iter().position(|file| *file == path) {
+            let cycle: Vec<String> = self.importing[start..]
+                .iter()
+                .chain(std::iter::once(&path))
+                .map(|file| file.display().to_string())
+                .collect();
+            runtime_error!("Import cycle detected: {}", cycle.join(" -> "));
+        }
+
+        let module = match self.modules.get(&path) {
+            Some(module) => module.clone(),
+            None => {
+                let module = self.load_module(path.clone(), namespace);
+                self.modules.insert(path, module.clone());
+                module
+            }
+        };
+
+        if self.scope_manager.contains_identifier(namespace) {
+            runtime_error!("Identifier {} already exists", namespace);
+        }
+        self.scope_manager
+            .insert_identifier(namespace.clone(), module.clone());
+        return module;
+    }
+
+    fn load_module(&mut self, path: PathBuf, namespace: &str) -> Value {
+        let input = fs::read_to_string(&path)
+            .unwrap_or_else(|_| runtime_error!("Could not read module {}", path.display()));
+        let tokens = Lexer::new(input).lex();
+        let mut root = Parser::new(tokens).parse();
+
+        let mut names = Vec::new();
+        declared_types(&root, &mut names);
+        qualify_types(&mut root, &names, namespace);
+
+        // Modules run in their own global scope; its bindings become the namespace.
+        let scope: Scope = Rc::new(RefCell::new(HashMap::new()));
+        let scopes = self.scope_manager.enter_environment(vec![scope.clone()]);
+        self.importing.push(path);
+        self.evaluate_program(&root);
+        self.importing.pop();
+        self.scope_manager.restore_environment(scopes);
+
+        let bindings = scope.borrow().clone();
+        return Value::Map(bindings);
+    }
+
+    fn store_struct(&mut self, root: &Node) {
+        let name = root.value.as_ref().expect("expected a struct name");
+        if self.structs.contains_key(name) {
+            runtime_error!("Struct {} already exists", name);
+        }
+
+        let fields: Vec<(String, String)> = root.children[0]
+            .children
+            .chunks(2)
+            .map(|pair| {
+                let annotation = pair[0].value.clone().expect("expected a type annotation");
+                let field = pair[1].value.clone().expect("expected an identifier");
+                (field, annotation)
+            })
+            .collect();
+        self.structs.insert(name.clone(), fields);
+    }
+
+    fn handle_struct_literal(&mut self, root: &Node) -> Value {
+        let name = root.value.as_ref().expect("expected a struct name");
+        let definition = self
+            .structs
+            .get(name)
+            .unwrap_or_else(|| runtime_error!("Struct {} not found", name))
+            .clone();
+
+        let mut values = HashMap::new();
+        for pair in root.children.chunks(2) {
+            let Value::String(key) = self.parse_value(&pair[0]) else {
+                runtime_error!("Expected a string");
+            };
+            if !definition.iter().any(|(field, _)| *field == key) {
+                runtime_error!("Unknown field {} for struct {}", key, name);
+            }
+            let value = self.evaluate_helper(&pair[1], &mut ControlFlow::None);
+            values.insert(key, value);
+        }
+
+        let fields = definition
+            .into_iter()
+            .map(|(field, annotation)| {
+                let value = values.remove(&field).unwrap_or_else(|| {
+                    runtime_error!("Missing field {} for struct {}", field, name)
+                });
+                (field, self.check_field_type(&annotation, value))
+            })
+            .collect();
+
+        return Value::Struct {
+            name: name.clone(),
+            fields,
+        };
+    }
+
+    fn store_enum(&mut self, root: &Node) {
+        let name = root.value.as_ref().expect("expected an enum name");
+        if self.enums.contains_key(name) {
+            runtime_error!("Enum {} already exists", name);
+        }
+
+        let variants: Vec<(String, Vec<String>)> = root
+            .children
+            .iter()
+            .map(|variant| {
+                let types = variant
+                    .children
+                    .iter()
+                    .map(|annotation| {
+                        annotation
+                            .value
+                            .clone()
+                            .expect("expected a type annotation")
+                    })
+                    .collect();
+                (variant.value.clone().expect("expected a variant"), types)
+            })
+            .collect();
+        self.enums.insert(name.clone(), variants);
+    }
+
+    fn handle_variant(&mut self, root: &Node) -> Value {
+        let name = root.value.as_ref().expect("expected an enum name");
+        let variant = root.children[0].value.as_ref().expect("expected a variant");
+        let types = self
+            .enums
+            .get(name)
+            .unwrap_or_else(|| runtime_error!("Enum {} not found", name))
+            .iter()
+            .find(|(candidate, _)| candidate == variant)
+            .map(|(_, types)| types.clone())
+            .unwrap_or_else(|| runtime_error!("Unknown variant {} for enum {}", variant, name));
+
+        let arguments = &root.children[1..];
+        if arguments.len() != types.len() {
+            runtime_error!(
+                "Variant {}.{} expects {} values, got {}",
+                name,
+                variant,
+                types.len(),
+                arguments.len()
+            );
+        }
+
+        let payload = arguments
+            .iter()
+            .zip(&types)
+            .map(|(argument, annotation)| {
+                let value = self.evaluate_helper(argument, &mut ControlFlow::None);
+                self.check_field_type(annotation, value)
+            })
+            .collect();
+
+        return Value::Enum {
+            name: name.clone(),
+            variant: variant.clone(),
+            payload,
+        };
+    }
+
+    fn check_field_type(&self, annotation: &str, value: Value) -> Value {
+        match value {
+            Value::Number(_)
+            | Value::BigNumber(_)
+            | Value::Float(_)
+            | Value::Rational(_)
+            | Value::Decimal(_)
+            | Value::Complex(_) => self.coerce_number(value, annotation),
+            Value::Range { .. } if annotation == "list" => range_to_list(&value),
+            _ if self.matches_type(annotation, &value) => value,
+            _ => runtime_error!("Type mismatch"),
+        }
+    }
+
     fn store_functions(&mut self, root: &Node) -> Value {
         for child in &root.children {
-            if child.node_type == NodeType::Declaration {
+            if child.node_type == NodeType::Struct {
+                self.store_struct(child);
+            } else if child.node_type == NodeType::Enum {
+                self.store_enum(child);
+            } else if child.node_type == NodeType::Declaration {
                 let identifier = child.children[0]
                     .value
                     .as_ref()
                     .expect("expected an identifier");
-                self.scope_manager.insert_identifier(
-                    identifier.clone(),
-                    Value::Function(child.children[1].clone()),
-                );
+                let function = Value::Function(Closure {
+                    function: child.children[1].clone(),
+                    environment: self.scope_manager.capture(),
+                });
+                self.scope_manager
+                    .insert_identifier(identifier.clone(), function);
             } else {
                 self.store_functions(child);
             }
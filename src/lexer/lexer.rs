@@ -27,6 +27,10 @@ pub struct Lexer<'a> {
     keywords: Vec<&'a str>,
     operators: Vec<&'a str>,
     current_line: i32,
+    escaped: bool,
+    // The previous string character was an unescaped `$`.
+    dollar: bool,
+    interpolation_depth: usize,
 }
 
 impl Lexer<'_> {
@@ -58,23 +62,56 @@ impl Lexer<'_> {
                 "return",
                 "bool",
                 "int",
+                "float",
+                "rational",
+                "decimal",
+                "complex",
+                "sqrt",
+                "abs",
+                "to",
+                "quantity",
+                "bin",
+                "hex",
+                "to_float",
+                "to_list",
+                "to_str",
+                "range",
                 "str",
                 "list",
                 "function",
                 "map",
                 "while",
+                "for",
+                "in",
+                "break",
+                "continue",
+                "match",
+                "struct",
+                "enum",
+                "import",
+                "try",
+                "catch",
+                "finally",
+                "throw",
+                "yield",
+                "generator",
+                "next",
             ],
             operators: vec![
-                "+", "-", "*", "/", "%", "=", "==", ">=", "<=", ">", "<", "!=", ":", ".",
+                "+", "-", "*", "/", "%", "=", "==", ">=", "<=", ">", "<", "!=", ":", ".", "&&",
+                "||", "!", "^", "**", "&", "|", "~", "<<", ">>", "..", "..=", "...", "|>", "=>",
             ],
             current_line: 0,
+            escaped: false,
+            dollar: false,
+            interpolation_depth: 0,
         }
     }
 
     pub fn lex(&mut self) -> Vec<Token> {
         use regex::Regex;
         let number = Regex::new(r"\d").unwrap();
-        let operator = Regex::new(r"[+\-*/=><!%:.]").unwrap();
+        let operator = Regex::new(r"[+\-*/=><!%:.&|^~]").unwrap();
         let whitespace = Regex::new(r"\s").unwrap();
         let left_paren = Regex::new(r"\(").unwrap();
         let right_paren = Regex::new(r"\)").unwrap();
@@ -86,6 +123,7 @@ impl Lexer<'_> {
         let comma: Regex = Regex::new(r",").unwrap();
         let string: Regex = Regex::new(r#"""#).unwrap();
         let semicolon = Regex::new(r";").unwrap();
+        let label = Regex::new(r"'").unwrap();
         let newline = Regex::new(r"\n").unwrap();
 
         let input = self.input.clone();
@@ -93,11 +131,8 @@ impl Lexer<'_> {
             self.current_line = i as i32 + 1;
             for c in line.chars() {
                 if self.state == State::String {
-                    if string.is_match(&c.to_string()) {
-                        self.buffer += &c.to_string();
-                        self.push_string();
-                        continue;
-                    }
+                    self.string_char(c);
+                    continue;
                 }
 
                 if self.buffer.ends_with("/") && c == '*' {
@@ -136,6 +171,9 @@ impl Lexer<'_> {
                     self.whitespace();
                 } else if semicolon.is_match(&c.to_string()) {
                     self.whitespace();
+                    if let Some(token) = self.tokens.last_mut() {
+                        token.terminated = true;
+                    }
                 } else if newline.is_match(&c.to_string()) {
                     self.whitespace();
                 } else if left_brace.is_match(&c.to_string()) {
@@ -150,6 +188,8 @@ impl Lexer<'_> {
                     self.comma(c);
                 } else if string.is_match(&c.to_string()) {
                     self.string(c);
+                } else if label.is_match(&c.to_string()) {
+                    self.label(c);
                 } else {
                     panic!("Invalid character: {}", c);
                 }
@@ -165,12 +205,16 @@ impl Lexer<'_> {
         if self.state == State::Operator {
             self.push_operator();
         }
+        if self.state == State::String {
+            panic!("Unterminated string literal");
+        }
 
         return self.tokens.clone();
     }
 
     fn number(&mut self, c: char) {
         if self.state == State::Alpha {
+            self.buffer += &c.to_string();
             return;
         }
         if self.state == State::Operator {
@@ -181,7 +225,42 @@ impl Lexer<'_> {
         self.buffer += &c.to_string();
     }
 
+    fn is_number_continuation(&self, c: char) -> bool {
+        if self.state != State::Number || self.buffer.ends_with('i') {
+            return false;
+        }
+        if self.buffer == "0" && matches!(c, 'x' | 'X' | 'b' | 'B' | 'o' | 'O') {
+            return true;
+        }
+        let prefix = self.buffer.get(..2).unwrap_or_default().to_lowercase();
+        if prefix == "0x" {
+            return c.is_ascii_hexdigit();
+        }
+        if prefix == "0b" || prefix == "0o" {
+            return false;
+        }
+        let has_exponent = self.buffer.contains(['e', 'E']);
+        match c {
+            '.' => !self.buffer.contains('.') && !has_exponent,
+            'e' | 'E' => !has_exponent,
+            '+' | '-' => self.buffer.ends_with(['e', 'E']),
+            'i' => !self.buffer.ends_with(['e', 'E', '+', '-']),
+            _ => false,
+        }
+    }
+
     fn operator(&mut self, c: char) {
+        if self.state == State::Number && c == '.' && self.buffer.ends_with('.') {
+            self.buffer.pop();
+            self.push_number();
+            self.state = State::Operator;
+            self.buffer += "..";
+            return;
+        }
+        if self.is_number_continuation(c) {
+            self.buffer += &c.to_string();
+            return;
+        }
         if self.state == State::Alpha {
             self.push_alpha();
         }
@@ -193,10 +272,6 @@ impl Lexer<'_> {
     }
 
     fn whitespace(&mut self) {
-        if self.state == State::String {
-            self.buffer += " ";
-            return;
-        }
         if self.state == State::Operator {
             self.push_operator();
         }
@@ -298,15 +373,29 @@ impl Lexer<'_> {
     }
 
     fn alpha(&mut self, c: char) {
+        if self.is_number_continuation(c) {
+            self.buffer += &c.to_string();
+            return;
+        }
         if self.state == State::Operator {
             self.push_operator();
         }
         if self.state == State::Number {
             self.push_number();
         }
-        if self.state == State::String {
-            self.buffer += &c.to_string();
-            return;
+        self.state = State::Alpha;
+        self.buffer += &c.to_string();
+    }
+
+    fn label(&mut self, c: char) {
+        if self.state == State::Alpha {
+            self.push_alpha();
+        }
+        if self.state == State::Number {
+            self.push_number();
+        }
+        if self.state == State::Operator {
+            self.push_operator();
         }
         self.state = State::Alpha;
         self.buffer += &c.to_string();
@@ -322,17 +411,37 @@ impl Lexer<'_> {
         if self.state == State::Operator {
             self.push_operator();
         }
-        if self.state == State::String {
-            self.buffer += &c.to_string();
-            self.push_string();
-            return;
-        }
         self.state = State::String;
         self.buffer += &c.to_string();
     }
 
+    // Text is kept raw; the parser decodes escapes and expands `${...}` interpolations.
+    fn string_char(&mut self, c: char) {
+        self.buffer += &c.to_string();
+        let dollar = std::mem::take(&mut self.dollar);
+        if self.escaped {
+            self.escaped = false;
+            return;
+        }
+        match c {
+            '\\' => self.escaped = true,
+            '$' => self.dollar = true,
+            '{' if self.interpolation_depth > 0 => self.interpolation_depth += 1,
+            '{' if dollar => self.interpolation_depth = 1,
+            '}' if self.interpolation_depth > 0 => self.interpolation_depth -= 1,
+            '"' if self.interpolation_depth == 0 => self.push_string(),
+            _ => {}
+        }
+    }
+
     fn push_alpha(&mut self) {
-        if self.keywords.contains(&self.buffer.as_str()) {
+        if let Some(label) = self.buffer.strip_prefix('\'') {
+            self.tokens.push(Token::new(
+                TokenType::Label,
+                label.to_string(),
+                self.current_line,
+            ));
+        } else if self.keywords.contains(&self.buffer.as_str()) {
             self.tokens.push(Token::new(
                 TokenType::Keyword,
                 self.buffer.clone(),
@@ -358,6 +467,16 @@ impl Lexer<'_> {
     }
 
     fn push_operator(&mut self) {
+        // Prefix operators directly after another operator (`&&!x`, `!!x`, `2**-1`) end up
+        // in the same buffer, so split them off the end.
+        let mut prefixes = Vec::new();
+        while !self.operators.contains(&self.buffer.as_str())
+            && self.buffer.len() > 1
+            && self.buffer.ends_with(['!', '~', '-'])
+        {
+            prefixes.push(self.buffer.pop().unwrap());
+        }
+
         if !self.operators.contains(&self.buffer.as_str()) {
             panic!("Invalid operator: {}", self.buffer,);
         }
@@ -366,6 +485,13 @@ impl Lexer<'_> {
             self.buffer.clone(),
             self.current_line,
         ));
+        for prefix in prefixes.into_iter().rev() {
+            self.tokens.push(Token::new(
+                TokenType::Operator,
+                prefix.to_string(),
+                self.current_line,
+            ));
+        }
         self.buffer = String::new();
     }
 
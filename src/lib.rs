@@ -3,3 +3,4 @@ pub mod lexer;
 pub mod node;
 pub mod parser;
 pub mod token;
+pub mod unit;
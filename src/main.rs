@@ -3,6 +3,7 @@ pub mod lexer;
 pub mod node;
 pub mod parser;
 pub mod token;
+pub mod unit;
 
 use std::{env, fs};
 
@@ -12,14 +13,30 @@ use parser::parser::Parser;
 
 fn main() {
     let mut interpreter = Interpreter::new();
-    let args: Vec<String> = env::args().collect();
+    let mut args = env::args().skip(1);
+    let mut positional = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if arg == "--precision" {
+            let precision = args
+                .next()
+                .and_then(|digits| digits.parse().ok())
+                .expect("--precision expects a number of digits");
+            interpreter.set_precision(precision);
+        } else if arg == "--complex" {
+            interpreter.set_complex_mode(true);
+        } else {
+            positional.push(arg);
+        }
+    }
 
-    if args.len() < 2 {
+    if positional.is_empty() {
         println!("Please provide a filename as an argument");
         return;
     }
 
-    let filename = args[2].trim();
+    let filename = positional.last().unwrap().trim();
+    interpreter.set_path(filename);
     let input = fs::read_to_string(filename).expect("Should have been able to read the file");
 
     let mut lexer = Lexer::new(input);
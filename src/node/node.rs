@@ -12,7 +12,24 @@ pub enum NodeType {
     Literal,
     If,
     While,
+    For,
+    Label,
+    Break,
+    Continue,
+    Match,
+    Arm,
+    Pattern,
     Function,
+    Struct,
+    StructLiteral,
+    Enum,
+    Variant,
+    Import,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Yield,
     Args,
     TypeAnnotation,
     Parameters,
@@ -23,6 +40,7 @@ pub enum NodeType {
     Map,
     Block,
     Return,
+    Unit,
 }
 
 #[derive(Debug, Clone, PartialEq)]
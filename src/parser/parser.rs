@@ -5,13 +5,26 @@ Program -> Statement Program
 
 Statement -> Let
     | If
+    | While
+    | For
+    | Label ':' While
+    | Label ':' For
     | Funk
+    | Struct
+    | Enum
+    | Import
+    | Try
     | Expr
     | FunctionCall
 
 Let -> Keyword Keyword Identifier '=' Expr
+    | Keyword Pattern '=' Expr
 
 Reassign -> Identifier '=' Expr
+    | Identifier Access+ '=' Expr
+Access -> '[' Expr ']'
+    | '.' Identifier
+    | '.' Identifier '(' Args ')'
 
 If -> Keyword Expr '{' Expr '}'
     | Keyword Expr '{' Expr '}' Else
@@ -19,7 +32,28 @@ If -> Keyword Expr '{' Expr '}'
 Else -> Keyword '{' Expr '}'
     | Keyword If
 
-Funk -> Keyword Identifier '(' Params ')' '{' Expr '}'
+For -> Keyword Identifier (',' Identifier)? Keyword Expr '{' Expr '}'
+
+Break -> Keyword Label?
+Continue -> Keyword Label?
+
+Funk -> Keyword Identifier Function
+    | Keyword '*' Identifier Function
+Struct -> Keyword Identifier '{' Params '}'
+Enum -> Keyword Identifier '{' Variant (',' Variant)* '}'
+Variant -> Identifier ('(' Keyword (',' Keyword)* ')')?
+StructLiteral -> Identifier '{' (Identifier ':' Expr (',' Identifier ':' Expr)*)? '}'
+VariantLiteral -> Identifier '.' Identifier ('(' Args ')')?
+
+Import -> Keyword String
+    | Keyword Identifier
+
+Try -> Keyword '{' Expr '}' Catch? Finally?
+Catch -> Keyword Identifier? '{' Expr '}'
+Finally -> Keyword '{' Expr '}'
+Throw -> Keyword Expr
+Yield -> Keyword Expr
+Function -> '(' Params ')' '{' Expr '}'
 
 Params -> Keyword Identifier ParamsTail
     | ε
@@ -33,31 +67,68 @@ Args -> ',' Expr ArgsTail
 
 FunctionCall -> Identifier '(' Args ')'
 
-Expr   -> Term ExprTail
-    | '-' Term ExprTail
+Expr   -> Pipe
+Pipe   -> Or ('|>' FunctionCall)*
+Or     -> And ('||' And)*
+And    -> BitOr ('&&' BitOr)*
+BitOr  -> BitXor ('|' BitXor)*
+BitXor -> BitAnd ('^' BitAnd)*
+BitAnd -> Comparison ('&' Comparison)*
+Comparison -> Shift (ComparisonOp Shift)*
+Shift  -> Sum (('<<' | '>>') Sum)*
+Sum    -> Term ExprTail
 ExprTail -> '+' Term ExprTail
          | '-' Term ExprTail
+         | '..' Sum
+         | '..=' Sum
          | ε
-Term   -> Factor TermTail
-TermTail -> '*' Factor TermTail
-         | '/' Factor TermTail
+Term   -> Power TermTail
+TermTail -> '*' Power TermTail
+         | '/' Power TermTail
          | ε
-Factor -> '(' Expr ')'
+Power  -> Factor '**' Power
+        | Factor
+Factor -> '-' Power
+        | '!' Power
+        | '~' Power
         | number
+        | Primary Access*
+Primary -> '(' Expr ')'
         | identifier
         | functionCall
         | Equality
         | String
         | Boolean
-        | Index
         | List
         | Map
+        | Break
+        | Continue
+        | Keyword Function
+        | Match
+        | Throw
+        | Yield
+        | Keyword '*' Function
 
 List -> '[' ListTail
 ListTail -> Expr ListTailTail
 ListTailTail -> ',' Expr ListTailTail
             | ']'
 
+Match -> Keyword Expr '{' Arm (',' Arm)* ','? '}'
+Arm -> Pattern (Keyword Expr)? '=>' (Expr | '{' Expr '}')
+Pattern -> '_'
+        | Identifier
+        | TypeKeyword Identifier
+        | '-'? number
+        | String
+        | Boolean
+        | '[' (Pattern (',' Pattern)* (',' '...' Identifier)?)? ']'
+        | '{' (MapPattern (',' MapPattern)*)? '}'
+MapPattern -> Key ':' Pattern
+        | Identifier
+
+String -> '"' (Text | Escape | '${' Expr '}')* '"'
+
 Map -> '{' MapTail
 MapTail -> Expr ':' Expr MapTailTail
 MapTailTail -> ',' Expr ':' Expr MapTailTail
@@ -65,14 +136,21 @@ MapTailTail -> ',' Expr ':' Expr MapTailTail
 
 */
 
+use std::iter::Peekable;
 use std::process::exit;
+use std::str::Chars;
 
+use crate::lexer::lexer::Lexer;
 use crate::node::node::{Node, NodeType};
 use crate::token::token::{Token, TokenType};
+use crate::unit::unit::is_unit;
 
 pub struct Parser {
     tokens: Vec<Token>,
     position: usize,
+    struct_names: Vec<String>,
+    enum_names: Vec<String>,
+    namespaces: Vec<String>,
 }
 
 impl Parser {
@@ -136,6 +214,11 @@ impl Parser {
     }
 
     fn expression(&mut self, root: &mut Node) {
+        if self.peek().token_type == TokenType::Label {
+            self.labeled_loop(root);
+            return;
+        }
+
         if self.peek().token_type == TokenType::Keyword {
             match self.peek().value.as_str() {
                 "let" => {
@@ -153,17 +236,56 @@ impl Parser {
                     self.while_statement(root);
                     return;
                 }
-                "funk" => {
+                "for" => {
+                    self.next();
+                    self.for_statement(root);
+                    return;
+                }
+                "struct" => {
+                    self.next();
+                    self.struct_declaration(root);
+                    return;
+                }
+                "enum" => {
+                    self.next();
+                    self.enum_declaration(root);
+                    return;
+                }
+                "import" => {
+                    self.next();
+                    self.import(root);
+                    return;
+                }
+                "try" => {
                     self.next();
-                    self.function_declaration(root);
+                    self.try_statement(root);
                     return;
                 }
+                "funk" => {
+                    self.next();
+                    let generator = self.generator_marker();
+                    if self.peek().token_type != TokenType::LeftParen {
+                        self.function_declaration(root);
+                        if generator {
+                            let declaration =
+                                root.children.last_mut().expect("expected a declaration");
+                            declaration.children[1].value = Some("generator".to_string());
+                        }
+                        return;
+                    }
+                    if generator {
+                        self.prev();
+                    }
+                    self.prev();
+                }
                 "print" | "println" => {
                     self.function_call(root);
                     return;
                 }
                 "true" | "false" | "is_bool" | "is_number" | "is_string" | "is_list" | "type"
-                | "head" | "tail" | "len" | "input" | "is_function" | "return" => {}
+                | "head" | "tail" | "len" | "input" | "is_function" | "return" | "rational"
+                | "to_float" | "sqrt" | "abs" | "to" | "bin" | "hex" | "to_list" | "to_str"
+                | "match" | "break" | "continue" | "throw" | "yield" | "next" => {}
                 _ => {
                     // panic!("Invalid keyword");
                     self.error(self.peek().clone(), "Invalid keyword");
@@ -171,39 +293,164 @@ impl Parser {
             }
         }
 
-        if !self.is_eof() {
-            if self.peek().token_type == TokenType::Operator && self.peek().value == "-".to_string()
+        if !self.is_eof() && self.peek().token_type == TokenType::Identifier {
+            self.next();
+            if !self.is_eof() {
+                if self.peek().token_type == TokenType::Operator && self.peek().value == "=" {
+                    self.prev();
+                    self.assignment(root, true);
+                    return;
+                }
+                if self.peek().token_type == TokenType::LeftBracket
+                    || (self.peek().token_type == TokenType::Operator && self.peek().value == ".")
+                {
+                    self.prev();
+                    let start = self.position;
+                    let mut target = Node {
+                        value: None,
+                        node_type: NodeType::Factor,
+                        children: Vec::new(),
+                    };
+                    self.factor(&mut target);
+                    if !self.is_eof()
+                        && self.peek().token_type == TokenType::Operator
+                        && self.peek().value == "="
+                    {
+                        self.target_assignment(root, target);
+                        return;
+                    }
+                    self.position = start;
+                    self.next();
+                }
+            }
+            self.prev();
+        }
+
+        self.pipeline(root);
+    }
+
+    fn pipeline(&mut self, root: &mut Node) {
+        let mut value = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        self.logical_or(&mut value);
+
+        while !self.is_eof()
+            && self.peek().token_type == TokenType::Operator
+            && self.peek().value == "|>"
+        {
+            self.next();
+            if self.peek().token_type != TokenType::Identifier
+                && self.peek().token_type != TokenType::Keyword
             {
-                let mut operator = Node {
-                    value: Some(self.peek().value.clone()),
-                    node_type: NodeType::Operation,
-                    children: Vec::new(),
-                };
-                self.next();
-                let mut term = Node {
+                self.error(self.peek().clone(), "Expected a function after |>");
+            }
+
+            self.next();
+            let has_args = !self.is_eof() && self.peek().token_type == TokenType::LeftParen;
+            self.prev();
+
+            let mut call = if has_args {
+                let mut wrapper = Node {
                     value: None,
-                    node_type: NodeType::Term,
+                    node_type: NodeType::Expression,
                     children: Vec::new(),
                 };
-                self.term(&mut term);
-                operator.children.push(term);
-                root.children.push(operator);
-                return;
-            }
-
-            if self.peek().token_type == TokenType::Identifier {
+                self.function_call(&mut wrapper);
+                wrapper.children.pop().expect("expected a function call")
+            } else {
+                let call = Node {
+                    value: Some(self.peek().value.clone()),
+                    node_type: NodeType::FunctionCall,
+                    children: vec![Node {
+                        value: None,
+                        node_type: NodeType::Args,
+                        children: Vec::new(),
+                    }],
+                };
                 self.next();
-                if !self.is_eof() {
-                    if self.peek().token_type == TokenType::Operator && self.peek().value == "=" {
-                        self.prev();
-                        self.assignment(root, true);
-                        return;
-                    }
-                }
-                self.prev();
-            }
+                call
+            };
+
+            call.children[0].children.insert(0, value);
+            value = Node {
+                value: None,
+                node_type: NodeType::Expression,
+                children: vec![call],
+            };
+        }
+
+        root.children.append(&mut value.children);
+    }
+
+    fn logical_or(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["||"], Parser::logical_and);
+    }
+
+    fn logical_and(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["&&"], Parser::bitwise_or);
+    }
+
+    fn bitwise_or(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["|"], Parser::bitwise_xor);
+    }
+
+    fn bitwise_xor(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["^"], Parser::bitwise_and);
+    }
+
+    fn bitwise_and(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["&"], Parser::comparison);
+    }
+
+    fn comparison(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["==", "!=", ">", ">=", "<", "<="], Parser::shift);
+    }
+
+    fn shift(&mut self, root: &mut Node) {
+        self.binary_operation(root, &["<<", ">>"], Parser::sum);
+    }
+
+    fn binary_operation(
+        &mut self,
+        root: &mut Node,
+        operators: &[&str],
+        operand: fn(&mut Parser, &mut Node),
+    ) {
+        let mut first = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        operand(self, &mut first);
+
+        while !self.is_eof()
+            && self.peek().token_type == TokenType::Operator
+            && operators.contains(&self.peek().value.as_str())
+        {
+            let mut operator = Node {
+                value: Some(self.peek().value.clone()),
+                node_type: NodeType::Operation,
+                children: vec![first],
+            };
+
+            self.next();
+            let mut second = Node {
+                value: None,
+                node_type: NodeType::Expression,
+                children: Vec::new(),
+            };
+            operand(self, &mut second);
+            operator.children.push(second);
+            first = operator;
         }
 
+        root.children.push(first);
+    }
+
+    fn sum(&mut self, root: &mut Node) {
         let mut term = Node {
             value: None,
             node_type: NodeType::Term,
@@ -236,28 +483,27 @@ impl Parser {
                     };
                     self.term(&mut term);
                     operator.children.push(term);
-                    self.expression_tail(root, &mut operator);
+                    self.expression_tail(root, &operator);
                 }
-                "==" | "!=" | ">" | ">=" | "<" | "<=" => {
+                ".." | "..=" => {
                     let mut operator = Node {
                         value: Some(self.peek().value.clone()),
                         node_type: NodeType::Operation,
                         children: Vec::new(),
                     };
-
                     operator.children.push(first_term.clone());
+
                     self.next();
-                    let mut term = Node {
+                    let mut end = Node {
                         value: None,
-                        node_type: NodeType::Term,
+                        node_type: NodeType::Expression,
                         children: Vec::new(),
                     };
-
-                    self.term(&mut term);
-                    operator.children.push(term);
-                    self.expression_tail(root, &mut operator);
+                    self.sum(&mut end);
+                    operator.children.push(end);
+                    root.children.push(operator);
                 }
-                _ => return,
+                _ => root.children.push(first_term.clone()),
             }
         } else {
             root.children.push(first_term.clone());
@@ -270,7 +516,7 @@ impl Parser {
             node_type: NodeType::Factor,
             children: Vec::new(),
         };
-        self.factor(&mut factor);
+        self.power(&mut factor);
         self.term_tail(root, &factor);
     }
 
@@ -295,9 +541,9 @@ impl Parser {
                         node_type: NodeType::Factor,
                         children: Vec::new(),
                     };
-                    self.factor(&mut factor);
+                    self.power(&mut factor);
                     operator.children.push(factor);
-                    self.term_tail(root, &mut operator);
+                    self.term_tail(root, &operator);
                 }
                 _ => root.children.push(first_term.clone()),
             }
@@ -306,7 +552,90 @@ impl Parser {
         }
     }
 
+    fn power(&mut self, root: &mut Node) {
+        let mut base = Node {
+            value: None,
+            node_type: NodeType::Factor,
+            children: Vec::new(),
+        };
+        self.factor(&mut base);
+
+        if self.is_eof() || self.peek().token_type != TokenType::Operator {
+            root.children.push(base);
+            return;
+        }
+        if self.peek().value != "**" {
+            root.children.push(base);
+            return;
+        }
+
+        let mut operator = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Operation,
+            children: vec![base],
+        };
+        self.next();
+        let mut exponent = Node {
+            value: None,
+            node_type: NodeType::Factor,
+            children: Vec::new(),
+        };
+        self.power(&mut exponent);
+        operator.children.push(exponent);
+        root.children.push(operator);
+    }
+
     fn factor(&mut self, root: &mut Node) {
+        if self.peek().token_type == TokenType::Operator
+            && (self.peek().value == "-" || self.peek().value == "!" || self.peek().value == "~")
+        {
+            let mut operator = Node {
+                value: Some(self.peek().value.clone()),
+                node_type: NodeType::Operation,
+                children: Vec::new(),
+            };
+            self.next();
+            let mut factor = Node {
+                value: None,
+                node_type: NodeType::Factor,
+                children: Vec::new(),
+            };
+            self.power(&mut factor);
+            operator.children.push(factor);
+            root.children.push(operator);
+        } else if self.peek().token_type == TokenType::Number {
+            let node = Node {
+                value: Some(self.peek().value.clone()),
+                node_type: NodeType::Literal,
+                children: Vec::new(),
+            };
+            let line = self.peek().line;
+            self.next();
+
+            if self.is_unit_ahead(line) {
+                let mut quantity = Node {
+                    value: Some("*".to_string()),
+                    node_type: NodeType::Operation,
+                    children: vec![node],
+                };
+                self.unit(&mut quantity);
+                root.children.push(quantity);
+            } else {
+                root.children.push(node);
+            }
+        } else {
+            let mut base = Node {
+                value: None,
+                node_type: NodeType::Factor,
+                children: Vec::new(),
+            };
+            self.primary(&mut base);
+            let node = self.postfix(base.children.pop().expect("expected a factor"));
+            root.children.push(node);
+        }
+    }
+
+    fn primary(&mut self, root: &mut Node) {
         if self.peek().token_type == TokenType::LeftParen {
             self.next();
             let mut expression = Node {
@@ -321,79 +650,27 @@ impl Parser {
             } else {
                 self.error(self.peek().clone(), "Expected right parenthesis");
             }
-        } else if self.peek().token_type == TokenType::Number {
-            let node = Node {
-                value: Some(self.peek().value.clone()),
-                node_type: NodeType::Literal,
-                children: Vec::new(),
-            };
-            root.children.push(node);
-            self.next();
         } else if self.peek().token_type == TokenType::Identifier {
             self.next();
-            if !self.is_eof() {
-                if self.peek().token_type == TokenType::LeftParen {
-                    self.prev();
-                    self.function_call(root);
-                    if !self.is_eof() {
-                        if self.peek().token_type == TokenType::LeftBracket {
-                            let children = root.children.clone();
-                            root.children.clear();
-
-                            let mut node = Node {
-                                value: None,
-                                node_type: NodeType::Index,
-                                children,
-                            };
-                            self.index(&mut node);
-                            root.children.push(node);
-                        }
-                    }
-                    return;
-                }
-                if self.peek().token_type == TokenType::LeftBracket {
-                    self.prev();
-                    let mut node = Node {
-                        value: None,
-                        node_type: NodeType::Index,
-                        children: vec![Node {
-                            value: Some(self.peek().value.clone()),
-                            node_type: NodeType::Identifier,
-                            children: Vec::new(),
-                        }],
-                    };
-                    self.next();
-                    self.index(&mut node);
-                    root.children.push(node);
-                    return;
-                }
-                if self.peek().token_type == TokenType::Operator {
-                    if self.peek().value == "." {
-                        self.prev();
-                        let mut node = Node {
-                            value: None,
-                            node_type: NodeType::MapIndex,
-                            children: vec![Node {
-                                value: Some(self.peek().value.clone()),
-                                node_type: NodeType::Identifier,
-                                children: Vec::new(),
-                            }],
-                        };
-                        self.next();
-                        self.map_index(&mut node);
-                        root.children.push(node);
-                        return;
-                    }
-                }
-            }
+            let has_args = !self.is_eof() && self.peek().token_type == TokenType::LeftParen;
             self.prev();
-            let node = Node {
-                value: Some(self.peek().value.clone()),
-                node_type: NodeType::Identifier,
-                children: Vec::new(),
-            };
-            root.children.push(node);
-            self.next();
+
+            if has_args {
+                self.function_call(root);
+            } else if self.struct_names.contains(&self.peek().value)
+                || self.is_qualified_struct_literal()
+            {
+                self.struct_literal(root);
+            } else if self.is_enum_variant() {
+                self.variant_literal(root);
+            } else {
+                root.children.push(Node {
+                    value: Some(self.peek().value.clone()),
+                    node_type: NodeType::Identifier,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
         } else if self.peek().token_type == TokenType::Keyword {
             match self.peek().value.as_str() {
                 "true" | "false" => {
@@ -427,18 +704,64 @@ impl Parser {
                     ret.children.push(expression);
                     root.children.push(ret);
                 }
+                "funk" => {
+                    self.next();
+                    let generator = self.generator_marker();
+                    self.function(root);
+                    if generator {
+                        let function = root.children.last_mut().expect("expected a function");
+                        function.value = Some("generator".to_string());
+                    }
+                }
+                "throw" | "yield" => {
+                    let node_type = if self.peek().value == "throw" {
+                        NodeType::Throw
+                    } else {
+                        NodeType::Yield
+                    };
+                    self.next();
+                    let mut expression = Node {
+                        value: None,
+                        node_type: NodeType::Expression,
+                        children: Vec::new(),
+                    };
+                    self.expression(&mut expression);
+                    root.children.push(Node {
+                        value: None,
+                        node_type,
+                        children: vec![expression],
+                    });
+                }
+                "match" => {
+                    self.next();
+                    self.match_expression(root);
+                }
+                "break" | "continue" => {
+                    let mut node = Node {
+                        value: None,
+                        node_type: if self.peek().value == "break" {
+                            NodeType::Break
+                        } else {
+                            NodeType::Continue
+                        },
+                        children: Vec::new(),
+                    };
+                    self.next();
+
+                    if !self.is_eof() && self.peek().token_type == TokenType::Label {
+                        node.value = Some(self.peek().value.clone());
+                        self.next();
+                    }
+                    root.children.push(node);
+                }
                 "is_bool" | "is_number" | "is_string" | "is_list" | "type" | "head" | "tail"
-                | "len" | "input" | "is_function" => self.function_call(root),
+                | "len" | "input" | "is_function" | "rational" | "to_float" | "sqrt" | "abs"
+                | "bin" | "hex" | "to_list" | "to_str" | "next" => self.function_call(root),
+                "to" => self.conversion(root),
                 _ => self.error(self.peek().clone(), "Invalid keyword"),
             }
         } else if self.peek().token_type == TokenType::String {
-            let node = Node {
-                value: Some(self.peek().value.clone()),
-                node_type: NodeType::Literal,
-                children: Vec::new(),
-            };
-            root.children.push(node);
-            self.next();
+            self.string(root);
         } else if self.peek().token_type == TokenType::LeftBracket {
             let mut list = Node {
                 value: None,
@@ -473,29 +796,26 @@ impl Parser {
             children: Vec::new(),
         };
 
-        if !reassign {
-            if self.peek().token_type != TokenType::Keyword {
-                self.error(self.peek().clone(), "Expected type keyword");
-            }
+        if !reassign
+            && (self.peek().token_type == TokenType::LeftBracket
+                || self.peek().token_type == TokenType::LeftBrace)
+        {
+            self.destructuring(root, operation);
+            return;
+        }
 
-            if self.peek().value != "bool"
-                && self.peek().value != "int"
-                && self.peek().value != "str"
-                && self.peek().value != "list"
-                && self.peek().value != "function"
-                && self.peek().value != "map"
-            {
+        if !reassign {
+            if !self.is_type_annotation() {
                 self.error(self.peek().clone(), "Expected type keyword");
             }
 
             let node = Node {
-                value: Some(self.peek().value.clone()),
+                value: Some(self.type_name()),
                 node_type: NodeType::TypeAnnotation,
                 children: Vec::new(),
             };
 
             operation.children.push(node);
-            self.next();
         }
 
         if self.peek().token_type != TokenType::Identifier {
@@ -529,6 +849,47 @@ impl Parser {
         }
     }
 
+    fn destructuring(&mut self, root: &mut Node, mut operation: Node) {
+        self.pattern(&mut operation);
+
+        if self.peek().token_type != TokenType::Operator || self.peek().value != "=" {
+            self.error(self.peek().clone(), "Expected assignment operator");
+        }
+        self.next();
+
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        self.expression(&mut expression);
+        operation.children.push(expression);
+
+        root.children.push(operation);
+    }
+
+    fn target_assignment(&mut self, root: &mut Node, mut target: Node) {
+        let mut operation = Node {
+            value: None,
+            node_type: NodeType::Reassignment,
+            children: vec![target
+                .children
+                .pop()
+                .expect("expected an assignment target")],
+        };
+
+        self.next();
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        self.expression(&mut expression);
+        operation.children.push(expression);
+
+        root.children.push(operation);
+    }
+
     fn list(&mut self, root: &mut Node) {
         if self.peek().token_type == TokenType::RightBracket {
             self.next();
@@ -573,11 +934,7 @@ impl Parser {
             return;
         }
 
-        let key = Node {
-            value: format!("\"{}\"", self.peek().value.clone()).into(),
-            node_type: NodeType::Literal,
-            children: Vec::new(),
-        };
+        let key = self.map_key();
 
         root.children.push(key);
         self.next();
@@ -601,6 +958,22 @@ impl Parser {
         self.map_tail(root);
     }
 
+    fn map_key(&mut self) -> Node {
+        let key = if self.peek().token_type == TokenType::String {
+            let token = self.peek().clone();
+            let raw = &token.value[1..token.value.len() - 1];
+            self.unescape(raw, &token)
+        } else {
+            self.peek().value.clone()
+        };
+
+        Node {
+            value: Some(format!("\"{}\"", key)),
+            node_type: NodeType::Literal,
+            children: Vec::new(),
+        }
+    }
+
     fn map_tail(&mut self, root: &mut Node) {
         if self.peek().token_type == TokenType::RightBrace {
             self.next();
@@ -618,11 +991,7 @@ impl Parser {
             return;
         }
 
-        let key = Node {
-            value: format!("\"{}\"", self.peek().value.clone()).into(),
-            node_type: NodeType::Literal,
-            children: Vec::new(),
-        };
+        let key = self.map_key();
 
         root.children.push(key);
 
@@ -647,6 +1016,305 @@ impl Parser {
         self.map_tail(root);
     }
 
+    fn match_expression(&mut self, root: &mut Node) {
+        let mut match_expression = Node {
+            value: Some("match".to_string()),
+            node_type: NodeType::Match,
+            children: Vec::new(),
+        };
+
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        self.expression(&mut expression);
+        match_expression.children.push(expression);
+
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+        self.next();
+
+        while self.peek().token_type != TokenType::RightBrace {
+            self.arm(&mut match_expression);
+
+            if self.peek().token_type == TokenType::Comma {
+                self.next();
+            } else if self.peek().token_type != TokenType::RightBrace {
+                self.error(self.peek().clone(), "Expected comma between match arms");
+            }
+        }
+        self.next();
+
+        root.children.push(match_expression);
+    }
+
+    fn arm(&mut self, root: &mut Node) {
+        let mut arm = Node {
+            value: None,
+            node_type: NodeType::Arm,
+            children: Vec::new(),
+        };
+
+        self.pattern(&mut arm);
+
+        let mut guard = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        if self.peek().token_type == TokenType::Keyword && self.peek().value == "if" {
+            self.next();
+            self.expression(&mut guard);
+        } else {
+            guard.children.push(Node {
+                value: Some("true".to_string()),
+                node_type: NodeType::Literal,
+                children: Vec::new(),
+            });
+        }
+        arm.children.push(guard);
+
+        if self.peek().token_type != TokenType::Operator || self.peek().value != "=>" {
+            self.error(self.peek().clone(), "Expected =>");
+        }
+        self.next();
+
+        if self.peek().token_type == TokenType::LeftBrace {
+            self.next();
+            let mut block = Node {
+                value: None,
+                node_type: NodeType::Block,
+                children: Vec::new(),
+            };
+            self.block(&mut block);
+            if self.peek().token_type != TokenType::RightBrace {
+                self.error(self.peek().clone(), "Expected right brace");
+            }
+            self.next();
+            arm.children.push(block);
+        } else {
+            let mut body = Node {
+                value: None,
+                node_type: NodeType::Expression,
+                children: Vec::new(),
+            };
+            self.expression(&mut body);
+            arm.children.push(body);
+        }
+
+        root.children.push(arm);
+    }
+
+    fn pattern(&mut self, root: &mut Node) {
+        let token = self.peek().clone();
+        let mut pattern = Node {
+            value: None,
+            node_type: NodeType::Pattern,
+            children: Vec::new(),
+        };
+
+        match token.token_type {
+            TokenType::Identifier if token.value == "_" => {
+                pattern.value = Some("wildcard".to_string());
+                self.next();
+            }
+            TokenType::Identifier if self.is_enum_variant() => {
+                pattern.value = Some("variant".to_string());
+                pattern.children.push(Node {
+                    value: Some(token.value.clone()),
+                    node_type: NodeType::Identifier,
+                    children: Vec::new(),
+                });
+                self.next();
+                self.next();
+                pattern.children.push(Node {
+                    value: Some(self.peek().value.clone()),
+                    node_type: NodeType::Identifier,
+                    children: Vec::new(),
+                });
+                self.next();
+
+                if !self.is_eof() && self.peek().token_type == TokenType::LeftParen {
+                    self.next();
+                    while self.peek().token_type != TokenType::RightParen {
+                        self.pattern(&mut pattern);
+                        if self.peek().token_type == TokenType::Comma {
+                            self.next();
+                        } else if self.peek().token_type != TokenType::RightParen {
+                            self.error(self.peek().clone(), "Expected comma");
+                        }
+                    }
+                    self.next();
+                }
+            }
+            TokenType::Identifier if !self.is_type_annotation() => {
+                pattern.value = Some("binding".to_string());
+                pattern.children.push(Node {
+                    value: Some(token.value.clone()),
+                    node_type: NodeType::Identifier,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
+            TokenType::Keyword if token.value == "true" || token.value == "false" => {
+                pattern.value = Some("literal".to_string());
+                pattern.children.push(Node {
+                    value: Some(token.value.clone()),
+                    node_type: NodeType::Literal,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
+            TokenType::Keyword | TokenType::Identifier if self.is_type_annotation() => {
+                pattern.value = Some("typed".to_string());
+                pattern.children.push(Node {
+                    value: Some(self.type_name()),
+                    node_type: NodeType::TypeAnnotation,
+                    children: Vec::new(),
+                });
+
+                if self.peek().token_type != TokenType::Identifier {
+                    self.error(self.peek().clone(), "Expected identifier");
+                }
+                pattern.children.push(Node {
+                    value: Some(self.peek().value.clone()),
+                    node_type: NodeType::Identifier,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
+            TokenType::Number => {
+                pattern.value = Some("literal".to_string());
+                pattern.children.push(Node {
+                    value: Some(token.value.clone()),
+                    node_type: NodeType::Literal,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
+            TokenType::Operator if token.value == "-" => {
+                self.next();
+                if self.peek().token_type != TokenType::Number {
+                    self.error(self.peek().clone(), "Expected number");
+                }
+                pattern.value = Some("literal".to_string());
+                pattern.children.push(Node {
+                    value: Some(format!("-{}", self.peek().value)),
+                    node_type: NodeType::Literal,
+                    children: Vec::new(),
+                });
+                self.next();
+            }
+            TokenType::String => {
+                pattern.value = Some("literal".to_string());
+                pattern.children.push(self.map_key());
+                self.next();
+            }
+            TokenType::LeftBracket => {
+                pattern.value = Some("list".to_string());
+                self.next();
+                while self.peek().token_type != TokenType::RightBracket {
+                    if self.peek().token_type == TokenType::Operator && self.peek().value == "..." {
+                        self.next();
+                        if self.peek().token_type != TokenType::Identifier {
+                            self.error(self.peek().clone(), "Expected identifier");
+                        }
+                        pattern.children.push(Node {
+                            value: Some("rest".to_string()),
+                            node_type: NodeType::Pattern,
+                            children: vec![Node {
+                                value: Some(self.peek().value.clone()),
+                                node_type: NodeType::Identifier,
+                                children: Vec::new(),
+                            }],
+                        });
+                        self.next();
+                        if self.peek().token_type != TokenType::RightBracket {
+                            self.error(self.peek().clone(), "Expected right bracket");
+                        }
+                        break;
+                    }
+
+                    self.pattern(&mut pattern);
+                    if self.peek().token_type == TokenType::Comma {
+                        self.next();
+                    } else if self.peek().token_type != TokenType::RightBracket {
+                        self.error(self.peek().clone(), "Expected comma");
+                    }
+                }
+                self.next();
+            }
+            TokenType::LeftBrace => {
+                pattern.value = Some("map".to_string());
+                self.next();
+                while self.peek().token_type != TokenType::RightBrace {
+                    let key = self.map_key();
+                    let shorthand = self.peek().token_type == TokenType::Identifier;
+                    let name = self.peek().value.clone();
+                    pattern.children.push(key);
+                    self.next();
+
+                    if self.peek().token_type == TokenType::Operator && self.peek().value == ":" {
+                        self.next();
+                        self.pattern(&mut pattern);
+                    } else if shorthand {
+                        pattern.children.push(Node {
+                            value: Some("binding".to_string()),
+                            node_type: NodeType::Pattern,
+                            children: vec![Node {
+                                value: Some(name),
+                                node_type: NodeType::Identifier,
+                                children: Vec::new(),
+                            }],
+                        });
+                    } else {
+                        self.error(self.peek().clone(), "Expected colon");
+                    }
+
+                    if self.peek().token_type == TokenType::Comma {
+                        self.next();
+                    } else if self.peek().token_type != TokenType::RightBrace {
+                        self.error(self.peek().clone(), "Expected comma");
+                    }
+                }
+                self.next();
+            }
+            _ => self.error(token, "Invalid pattern"),
+        }
+
+        root.children.push(pattern);
+    }
+
+    fn labeled_loop(&mut self, root: &mut Node) {
+        let mut label = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Label,
+            children: Vec::new(),
+        };
+        self.next();
+
+        if self.peek().token_type != TokenType::Operator || self.peek().value != ":" {
+            self.error(self.peek().clone(), "Expected colon after label");
+        }
+        self.next();
+
+        match self.peek().value.as_str() {
+            "while" => {
+                self.next();
+                self.while_statement(&mut label);
+            }
+            "for" => {
+                self.next();
+                self.for_statement(&mut label);
+            }
+            _ => self.error(self.peek().clone(), "Expected a loop after label"),
+        }
+
+        root.children.push(label);
+    }
+
     fn while_statement(&mut self, root: &mut Node) {
         let mut while_statement = Node {
             value: Some("while".to_string()),
@@ -688,13 +1356,38 @@ impl Parser {
         root.children.push(while_statement);
     }
 
-    fn if_statement(&mut self, root: &mut Node) {
-        let mut if_statement = Node {
-            value: Some("if".to_string()),
-            node_type: NodeType::If,
+    fn for_statement(&mut self, root: &mut Node) {
+        let mut for_statement = Node {
+            value: Some("for".to_string()),
+            node_type: NodeType::For,
             children: Vec::new(),
         };
 
+        loop {
+            if self.peek().token_type != TokenType::Identifier {
+                self.error(self.peek().clone(), "Expected loop variable");
+            }
+
+            for_statement.children.push(Node {
+                value: Some(self.peek().value.clone()),
+                node_type: NodeType::Identifier,
+                children: Vec::new(),
+            });
+            self.next();
+
+            if self.peek().token_type == TokenType::Comma {
+                self.next();
+            } else {
+                break;
+            }
+        }
+
+        if self.peek().token_type != TokenType::Keyword || self.peek().value != "in" {
+            self.error(self.peek().clone(), "Expected in");
+        }
+
+        self.next();
+
         let mut expression = Node {
             value: None,
             node_type: NodeType::Expression,
@@ -722,31 +1415,137 @@ impl Parser {
         }
 
         self.next();
-        if_statement.children.push(expression);
-        if_statement.children.push(block);
 
-        if !self.is_eof() {
-            if self.peek().token_type == TokenType::Keyword {
-                match self.peek().value.as_str() {
-                    "else" => {
-                        self.next();
-                        self.else_statement(&mut if_statement);
-                    }
-                    _ => {}
-                }
-            }
-        }
+        for_statement.children.push(expression);
+        for_statement.children.push(block);
 
-        root.children.push(if_statement);
+        root.children.push(for_statement);
     }
 
-    fn else_statement(&mut self, if_statement: &mut Node) {
-        if self.peek().token_type != TokenType::LeftBrace {
-            self.error(self.peek().clone(), "Expected left brace");
-        }
-
-        let mut else_block = Node {
-            value: None,
+    fn if_statement(&mut self, root: &mut Node) {
+        let mut if_statement = Node {
+            value: Some("if".to_string()),
+            node_type: NodeType::If,
+            children: Vec::new(),
+        };
+
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+
+        self.expression(&mut expression);
+
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+
+        self.next();
+
+        let mut block = Node {
+            value: None,
+            node_type: NodeType::Block,
+            children: Vec::new(),
+        };
+
+        self.block(&mut block);
+
+        if self.peek().token_type != TokenType::RightBrace {
+            self.error(self.peek().clone(), "Expected right brace");
+        }
+
+        self.next();
+        if_statement.children.push(expression);
+        if_statement.children.push(block);
+
+        if !self.is_eof() {
+            if self.peek().token_type == TokenType::Keyword {
+                match self.peek().value.as_str() {
+                    "else" => {
+                        self.next();
+                        self.else_statement(&mut if_statement);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        root.children.push(if_statement);
+    }
+
+    fn try_statement(&mut self, root: &mut Node) {
+        let mut try_statement = Node {
+            value: None,
+            node_type: NodeType::Try,
+            children: vec![self.braced_block()],
+        };
+
+        if !self.is_eof()
+            && self.peek().token_type == TokenType::Keyword
+            && self.peek().value == "catch"
+        {
+            self.next();
+            let mut catch = Node {
+                value: None,
+                node_type: NodeType::Catch,
+                children: Vec::new(),
+            };
+            if self.peek().token_type == TokenType::Identifier {
+                catch.value = Some(self.peek().value.clone());
+                self.next();
+            }
+            catch.children.push(self.braced_block());
+            try_statement.children.push(catch);
+        }
+
+        if !self.is_eof()
+            && self.peek().token_type == TokenType::Keyword
+            && self.peek().value == "finally"
+        {
+            self.next();
+            try_statement.children.push(Node {
+                value: None,
+                node_type: NodeType::Finally,
+                children: vec![self.braced_block()],
+            });
+        }
+
+        if try_statement.children.len() == 1 {
+            self.error(self.peek().clone(), "Expected catch or finally");
+        }
+
+        root.children.push(try_statement);
+    }
+
+    fn braced_block(&mut self) -> Node {
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+        self.next();
+
+        let mut block = Node {
+            value: None,
+            node_type: NodeType::Block,
+            children: Vec::new(),
+        };
+        self.block(&mut block);
+
+        if self.peek().token_type != TokenType::RightBrace {
+            self.error(self.peek().clone(), "Expected right brace");
+        }
+        self.next();
+
+        block
+    }
+
+    fn else_statement(&mut self, if_statement: &mut Node) {
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+
+        let mut else_block = Node {
+            value: None,
             node_type: NodeType::Block,
             children: Vec::new(),
         };
@@ -783,6 +1582,193 @@ impl Parser {
         operation.children.push(identifier);
         self.next();
 
+        self.function(&mut operation);
+        root.children.push(operation);
+    }
+
+    fn struct_declaration(&mut self, root: &mut Node) {
+        if self.peek().token_type != TokenType::Identifier {
+            self.error(self.peek().clone(), "Expected identifier");
+        }
+
+        let mut declaration = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Struct,
+            children: Vec::new(),
+        };
+        self.next();
+
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+        self.next();
+
+        let mut fields = Node {
+            value: None,
+            node_type: NodeType::Parameters,
+            children: Vec::new(),
+        };
+
+        if self.peek().token_type != TokenType::RightBrace {
+            self.parameters(&mut fields);
+        }
+
+        if self.peek().token_type != TokenType::RightBrace {
+            self.error(self.peek().clone(), "Expected right brace");
+        }
+        self.next();
+
+        declaration.children.push(fields);
+        root.children.push(declaration);
+    }
+
+    fn import(&mut self, root: &mut Node) {
+        let token = self.peek().clone();
+        let path = match token.token_type {
+            TokenType::String => self.unescape(&token.value[1..token.value.len() - 1], &token),
+            TokenType::Identifier => format!("{}.calc", token.value),
+            _ => self.error(token, "Expected a module name or path"),
+        };
+        self.next();
+
+        let namespace = Parser::namespace(&path)
+            .unwrap_or_else(|| self.error(token.clone(), "Invalid module path"));
+
+        root.children.push(Node {
+            value: Some(path),
+            node_type: NodeType::Import,
+            children: vec![Node {
+                value: Some(namespace),
+                node_type: NodeType::Identifier,
+                children: Vec::new(),
+            }],
+        });
+    }
+
+    fn enum_declaration(&mut self, root: &mut Node) {
+        if self.peek().token_type != TokenType::Identifier {
+            self.error(self.peek().clone(), "Expected identifier");
+        }
+
+        let mut declaration = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Enum,
+            children: Vec::new(),
+        };
+        self.next();
+
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+        self.next();
+
+        while self.peek().token_type != TokenType::RightBrace {
+            if self.peek().token_type != TokenType::Identifier {
+                self.error(self.peek().clone(), "Expected identifier");
+            }
+
+            let mut variant = Node {
+                value: Some(self.peek().value.clone()),
+                node_type: NodeType::Identifier,
+                children: Vec::new(),
+            };
+            self.next();
+
+            if self.peek().token_type == TokenType::LeftParen {
+                self.next();
+                while self.peek().token_type != TokenType::RightParen {
+                    if !self.is_type_annotation() {
+                        self.error(self.peek().clone(), "Expected type keyword");
+                    }
+                    variant.children.push(Node {
+                        value: Some(self.type_name()),
+                        node_type: NodeType::TypeAnnotation,
+                        children: Vec::new(),
+                    });
+
+                    if self.peek().token_type == TokenType::Comma {
+                        self.next();
+                    } else if self.peek().token_type != TokenType::RightParen {
+                        self.error(self.peek().clone(), "Expected comma");
+                    }
+                }
+                self.next();
+            }
+            declaration.children.push(variant);
+
+            if self.peek().token_type == TokenType::Comma {
+                self.next();
+            } else if self.peek().token_type != TokenType::RightBrace {
+                self.error(self.peek().clone(), "Expected comma");
+            }
+        }
+        self.next();
+
+        root.children.push(declaration);
+    }
+
+    fn variant_literal(&mut self, root: &mut Node) {
+        let mut literal = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Variant,
+            children: Vec::new(),
+        };
+        self.next();
+        self.next();
+
+        if self.peek().token_type != TokenType::Identifier {
+            self.error(self.peek().clone(), "Expected identifier");
+        }
+        literal.children.push(Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::Identifier,
+            children: Vec::new(),
+        });
+        self.next();
+
+        if !self.is_eof() && self.peek().token_type == TokenType::LeftParen {
+            self.next();
+            let mut args = Node {
+                value: None,
+                node_type: NodeType::Args,
+                children: Vec::new(),
+            };
+            self.args(&mut args);
+            if self.peek().token_type != TokenType::RightParen {
+                self.error(self.peek().clone(), "Expected right parenthesis");
+            }
+            self.next();
+            literal.children.extend(args.children);
+        }
+        root.children.push(literal);
+    }
+
+    fn struct_literal(&mut self, root: &mut Node) {
+        let mut literal = Node {
+            value: Some(self.type_name()),
+            node_type: NodeType::StructLiteral,
+            children: Vec::new(),
+        };
+
+        if self.peek().token_type != TokenType::LeftBrace {
+            self.error(self.peek().clone(), "Expected left brace");
+        }
+        self.next();
+
+        self.map(&mut literal);
+        root.children.push(literal);
+    }
+
+    // `funk*` declares a generator function.
+    fn generator_marker(&mut self) -> bool {
+        if self.peek().token_type == TokenType::Operator && self.peek().value == "*" {
+            self.next();
+            return true;
+        }
+        false
+    }
+
+    fn function(&mut self, root: &mut Node) {
         let mut function = Node {
             value: None,
             node_type: NodeType::Function,
@@ -832,35 +1818,22 @@ impl Parser {
         self.next();
 
         function.children.push(block);
-        operation.children.push(function);
-        root.children.push(operation);
+        root.children.push(function);
     }
 
     fn parameters(&mut self, root: &mut Node) {
-        if self.peek().token_type != TokenType::Keyword {
-            self.error(self.peek().clone(), "Expected type keyword");
-        }
-
-        if self.peek().value != "bool"
-            && self.peek().value != "int"
-            && self.peek().value != "str"
-            && self.peek().value != "list"
-            && self.peek().value != "function"
-            && self.peek().value != "map"
-        {
+        if !self.is_type_annotation() {
             self.error(self.peek().clone(), "Expected type keyword");
         }
 
         let node = Node {
-            value: Some(self.peek().value.clone()),
+            value: Some(self.type_name()),
             node_type: NodeType::TypeAnnotation,
             children: Vec::new(),
         };
 
         root.children.push(node);
 
-        self.next();
-
         if self.peek().token_type != TokenType::Identifier {
             self.error(self.peek().clone(), "Expected identifier");
         }
@@ -943,6 +1916,51 @@ impl Parser {
         self.args(root);
     }
 
+    fn postfix(&mut self, mut node: Node) -> Node {
+        while !self.is_eof() {
+            if self.peek().token_type == TokenType::LeftBracket {
+                node = Node {
+                    value: None,
+                    node_type: NodeType::Index,
+                    children: vec![node],
+                };
+                self.index(&mut node);
+            } else if self.peek().token_type == TokenType::Operator && self.peek().value == "." {
+                node = Node {
+                    value: None,
+                    node_type: NodeType::MapIndex,
+                    children: vec![node],
+                };
+                self.map_index(&mut node);
+            } else if self.peek().token_type == TokenType::LeftParen
+                && !self.tokens[self.position - 1].terminated
+            {
+                // `fs[0](4)`, `module.function(...)` and `make()(2)` call whatever
+                // the expression evaluates to.
+                let mut args = Node {
+                    value: None,
+                    node_type: NodeType::Args,
+                    children: Vec::new(),
+                };
+                self.next();
+                self.args(&mut args);
+                if self.peek().token_type != TokenType::RightParen {
+                    self.error(self.peek().clone(), "Expected right parenthesis");
+                }
+                self.next();
+
+                node = Node {
+                    value: None,
+                    node_type: NodeType::FunctionCall,
+                    children: vec![args, node],
+                };
+            } else {
+                break;
+            }
+        }
+        node
+    }
+
     fn map_index(&mut self, root: &mut Node) {
         if self.peek().token_type != TokenType::Operator {
             self.error(self.peek().clone(), "Expected dot operator");
@@ -954,7 +1972,11 @@ impl Parser {
 
         self.next();
 
-        if self.peek().token_type != TokenType::Identifier {
+        // Like map keys, fields may be named after keywords: `node.next`.
+        if !matches!(
+            self.peek().token_type,
+            TokenType::Identifier | TokenType::Keyword
+        ) {
             self.error(self.peek().clone(), "Expected identifier");
         }
 
@@ -969,6 +1991,112 @@ impl Parser {
         self.next();
     }
 
+    fn is_unit_ahead(&self, line: i32) -> bool {
+        // A unit never starts a new statement: `5; s + 1` does not read `s` as seconds.
+        if self.tokens[self.position - 1].terminated {
+            return false;
+        }
+        let used_as_identifier = self.tokens.get(self.position + 1).is_some_and(|next| {
+            matches!(
+                next.token_type,
+                TokenType::LeftParen | TokenType::LeftBracket
+            ) || (next.token_type == TokenType::Operator
+                && (next.value == "=" || next.value == "."))
+        });
+
+        !self.is_eof()
+            && self.peek().token_type == TokenType::Identifier
+            && self.peek().line == line
+            && is_unit(&self.peek().value)
+            && !used_as_identifier
+    }
+
+    fn unit(&mut self, root: &mut Node) {
+        let line = self.peek().line;
+        let mut name = String::new();
+
+        loop {
+            if !self.is_unit_ahead(line) {
+                self.error(self.peek().clone(), "Expected unit");
+            }
+            name += &self.peek().value;
+            self.next();
+
+            if !self.is_eof() && self.peek().value == "^" {
+                self.next();
+                if self.peek().token_type != TokenType::Number {
+                    self.error(self.peek().clone(), "Expected unit exponent");
+                }
+                name += &format!("^{}", self.peek().value);
+                self.next();
+            }
+
+            if self.is_eof() || (self.peek().value != "*" && self.peek().value != "/") {
+                break;
+            }
+            self.next();
+            if !self.is_unit_ahead(line) {
+                self.prev();
+                break;
+            }
+            self.prev();
+            name += &self.peek().value;
+            self.next();
+        }
+
+        let node = Node {
+            value: Some(name),
+            node_type: NodeType::Unit,
+            children: Vec::new(),
+        };
+        root.children.push(node);
+    }
+
+    fn conversion(&mut self, root: &mut Node) {
+        let mut operation = Node {
+            value: Some(self.peek().value.clone()),
+            node_type: NodeType::FunctionCall,
+            children: Vec::new(),
+        };
+
+        self.next();
+
+        if self.peek().token_type != TokenType::LeftParen {
+            self.error(self.peek().clone(), "Expected left parenthesis");
+        }
+
+        let mut args = Node {
+            value: None,
+            node_type: NodeType::Args,
+            children: Vec::new(),
+        };
+
+        self.next();
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        self.expression(&mut expression);
+        args.children.push(expression);
+
+        if self.peek().token_type != TokenType::Comma {
+            self.error(self.peek().clone(), "Expected comma");
+        }
+
+        self.next();
+        self.unit(&mut args);
+
+        if self.peek().token_type != TokenType::RightParen {
+            self.error(self.peek().clone(), "Expected right parenthesis");
+        }
+
+        self.next();
+
+        operation.children.push(args);
+        root.children.push(operation);
+    }
+
     fn index(&mut self, root: &mut Node) {
         if self.peek().token_type != TokenType::LeftBracket {
             self.error(self.peek().clone(), "Expected left bracket");
@@ -992,6 +2120,207 @@ impl Parser {
         self.next();
     }
 
+    fn string(&mut self, root: &mut Node) {
+        let token = self.peek().clone();
+        self.next();
+
+        let raw = &token.value[1..token.value.len() - 1];
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = raw.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => text.push(self.escape(&mut chars, &token)),
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    let mut code = String::new();
+                    let mut depth = 1;
+                    for c in chars.by_ref() {
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            _ => {}
+                        }
+                        if depth == 0 {
+                            break;
+                        }
+                        code.push(c);
+                    }
+
+                    parts.push(Node {
+                        value: Some(format!("\"{}\"", text)),
+                        node_type: NodeType::Literal,
+                        children: Vec::new(),
+                    });
+                    text.clear();
+                    parts.push(self.interpolation(code, &token));
+                }
+                _ => text.push(c),
+            }
+        }
+
+        if parts.is_empty() || !text.is_empty() {
+            parts.push(Node {
+                value: Some(format!("\"{}\"", text)),
+                node_type: NodeType::Literal,
+                children: Vec::new(),
+            });
+        }
+
+        let mut parts = parts.into_iter();
+        let mut node = parts.next().expect("expected a string part");
+        for part in parts {
+            node = Node {
+                value: Some("+".to_string()),
+                node_type: NodeType::Operation,
+                children: vec![node, part],
+            };
+        }
+        root.children.push(node);
+    }
+
+    fn interpolation(&self, code: String, token: &Token) -> Node {
+        let mut tokens = Lexer::new(code).lex();
+        if tokens.is_empty() {
+            self.error(token.clone(), "Empty interpolation");
+        }
+        for inner in tokens.iter_mut() {
+            inner.line = token.line;
+        }
+
+        let mut parser = Parser::new(tokens);
+        let mut expression = Node {
+            value: None,
+            node_type: NodeType::Expression,
+            children: Vec::new(),
+        };
+        parser.expression(&mut expression);
+        if !parser.is_eof() {
+            parser.error(parser.peek().clone(), "Unexpected token in interpolation");
+        }
+
+        Node {
+            value: Some("to_str".to_string()),
+            node_type: NodeType::FunctionCall,
+            children: vec![Node {
+                value: None,
+                node_type: NodeType::Args,
+                children: vec![expression],
+            }],
+        }
+    }
+
+    fn unescape(&self, raw: &str, token: &Token) -> String {
+        let mut text = String::new();
+        let mut chars = raw.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                text.push(self.escape(&mut chars, token));
+            } else {
+                text.push(c);
+            }
+        }
+        text
+    }
+
+    fn escape(&self, chars: &mut Peekable<Chars>, token: &Token) -> char {
+        match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some('\\') => '\\',
+            Some('"') => '"',
+            Some('$') => '$',
+            Some('u') if chars.next() == Some('{') => {
+                let digits: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .unwrap_or_else(|| self.error(token.clone(), "Invalid unicode escape"))
+            }
+            _ => self.error(token.clone(), "Invalid escape sequence"),
+        }
+    }
+
+    fn is_type_annotation(&self) -> bool {
+        let token = self.peek();
+        match token.token_type {
+            TokenType::Keyword => matches!(
+                token.value.as_str(),
+                "bool"
+                    | "int"
+                    | "float"
+                    | "rational"
+                    | "decimal"
+                    | "complex"
+                    | "quantity"
+                    | "str"
+                    | "list"
+                    | "range"
+                    | "function"
+                    | "generator"
+                    | "map"
+            ),
+            TokenType::Identifier if self.is_qualified_type() => {
+                // `utils.Shape.Circle(r)` names a variant, not a type.
+                !self.tokens.get(self.position + 3).is_some_and(|next| {
+                    next.token_type == TokenType::LeftParen || next.value == "."
+                })
+            }
+            TokenType::Identifier => {
+                self.struct_names.contains(&token.value) || self.enum_names.contains(&token.value)
+            }
+            _ => false,
+        }
+    }
+
+    // `utils.Point` names a type declared in the module imported as `utils`.
+    fn is_qualified_type(&self) -> bool {
+        let token = self.peek();
+        let dot = self.tokens.get(self.position + 1);
+        let name = self.tokens.get(self.position + 2);
+        token.token_type == TokenType::Identifier
+            && self.namespaces.contains(&token.value)
+            && dot.is_some_and(|dot| dot.token_type == TokenType::Operator && dot.value == ".")
+            && name.is_some_and(|name| name.token_type == TokenType::Identifier)
+    }
+
+    // Unlike a local struct, an imported one is only known by the shape of its literal:
+    // `utils.Point {}` or `utils.Point { field: ... }`.
+    fn is_qualified_struct_literal(&self) -> bool {
+        let brace = self.tokens.get(self.position + 3);
+        let key = self.tokens.get(self.position + 4);
+        let colon = self.tokens.get(self.position + 5);
+        self.is_qualified_type()
+            && brace.is_some_and(|brace| brace.token_type == TokenType::LeftBrace)
+            && key.is_some_and(|key| {
+                key.token_type == TokenType::RightBrace
+                    || (matches!(key.token_type, TokenType::Identifier | TokenType::Keyword)
+                        && colon.is_some_and(|colon| colon.value == ":"))
+            })
+    }
+
+    fn type_name(&mut self) -> String {
+        let mut name = self.peek().value.clone();
+        if self.is_qualified_type() {
+            self.next();
+            self.next();
+            name = format!("{}.{}", name, self.peek().value);
+        }
+        self.next();
+        name
+    }
+
+    fn is_enum_variant(&self) -> bool {
+        let token = self.peek();
+        let next = self.tokens.get(self.position + 1);
+        token.token_type == TokenType::Identifier
+            && self.enum_names.contains(&token.value)
+            && next.is_some_and(|next| next.token_type == TokenType::Operator && next.value == ".")
+    }
+
     fn peek(&self) -> &Token {
         &self.tokens[self.position]
     }
@@ -1000,7 +2329,7 @@ impl Parser {
         self.position == self.tokens.len()
     }
 
-    fn error(&self, token: Token, message: &str) {
+    fn error(&self, token: Token, message: &str) -> ! {
         eprintln!(
             "Error found near line {} with value '{}': {}",
             token.line, token.value, message
@@ -1009,9 +2338,34 @@ impl Parser {
     }
 
     pub fn new(tokens: Vec<Token>) -> Parser {
+        // Type names are collected up front so `Name { ... }` can be told apart from a block.
+        let struct_names = Parser::declared_names(&tokens, "struct");
+        let enum_names = Parser::declared_names(&tokens, "enum");
+        let namespaces = Parser::declared_names(&tokens, "import")
+            .iter()
+            .filter_map(|module| Parser::namespace(module.trim_matches('"')))
+            .collect();
+
         Parser {
             tokens,
             position: 0,
+            struct_names,
+            enum_names,
+            namespaces,
         }
     }
+
+    // The namespace of a module is its file name without the extension.
+    fn namespace(path: &str) -> Option<String> {
+        let stem = std::path::Path::new(path).file_stem()?.to_str()?;
+        Some(stem.to_string())
+    }
+
+    fn declared_names(tokens: &[Token], keyword: &str) -> Vec<String> {
+        tokens
+            .windows(2)
+            .filter(|pair| pair[0].token_type == TokenType::Keyword && pair[0].value == keyword)
+            .map(|pair| pair[1].value.clone())
+            .collect()
+    }
 }
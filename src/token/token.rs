@@ -1,6 +1,14 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_complex::Complex;
+use num_rational::BigRational;
 
 use crate::node::node::Node;
+use crate::unit::unit::Quantity;
 
 #[derive(PartialEq, Clone, Debug)]
 pub enum TokenType {
@@ -16,25 +24,134 @@ pub enum TokenType {
     RightBracket,
     Comma,
     String,
+    Label,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Value {
     Number(i32),
+    BigNumber(BigInt),
     String(String),
     Boolean(bool),
     Float(f32),
+    Rational(BigRational),
+    Decimal(BigRational),
+    Complex(Complex<f32>),
+    Quantity(Quantity),
+    Range {
+        start: i32,
+        end: i32,
+        inclusive: bool,
+    },
     List(Vec<Value>),
-    Function(Node),
+    Function(Closure),
+    Generator(Generator),
     Map(HashMap<String, Value>),
+    Struct {
+        name: String,
+        fields: Vec<(String, Value)>,
+    },
+    Enum {
+        name: String,
+        variant: String,
+        payload: Vec<Value>,
+    },
     Null,
 }
 
+pub type Scope = Rc<RefCell<HashMap<String, Value>>>;
+
+#[derive(Clone)]
+pub struct Closure {
+    pub function: Node,
+    pub environment: Vec<Scope>,
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        self.function == other.function
+            && self.environment.len() == other.environment.len()
+            && self
+                .environment
+                .iter()
+                .zip(&other.environment)
+                .all(|(first, second)| Rc::ptr_eq(first, second))
+    }
+}
+
+// The environment can contain the closure itself, so it is left out of the output.
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("function", &self.function)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Clone)]
+pub struct Generator {
+    pub state: Rc<RefCell<GeneratorState>>,
+}
+
+pub struct GeneratorState {
+    pub body: Rc<Node>,
+    pub scopes: Vec<Scope>,
+    pub frames: Vec<Frame>,
+    pub status: GeneratorStatus,
+}
+
+#[derive(PartialEq)]
+pub enum GeneratorStatus {
+    Suspended,
+    Running,
+    Done,
+}
+
+// Where a suspended generator continues; the innermost construct is pushed first.
+pub enum Frame {
+    Block(usize),
+    Branch(usize),
+    Loop,
+    For(LoopSource),
+}
+
+pub enum LoopSource {
+    Items(Box<dyn Iterator<Item = Vec<Value>>>),
+    Generator(Generator),
+}
+
+impl Generator {
+    pub fn new(body: Rc<Node>, scopes: Vec<Scope>) -> Generator {
+        Generator {
+            state: Rc::new(RefCell::new(GeneratorState {
+                body,
+                scopes,
+                frames: Vec::new(),
+                status: GeneratorStatus::Suspended,
+            })),
+        }
+    }
+}
+
+impl PartialEq for Generator {
+    fn eq(&self, other: &Generator) -> bool {
+        Rc::ptr_eq(&self.state, &other.state)
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Generator").finish_non_exhaustive()
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Token {
     pub token_type: TokenType,
     pub value: String,
     pub line: i32,
+    // A `;` follows this token. The lexer drops `;`, but statements must not run together.
+    pub terminated: bool,
 }
 
 impl Token {
@@ -43,6 +160,7 @@ impl Token {
             token_type,
             value,
             line,
+            terminated: false,
         }
     }
 }
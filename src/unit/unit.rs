@@ -0,0 +1,151 @@
+/// Exponents of the SI base dimensions, in the order of `BASE_UNITS`.
+pub type Dimension = [i8; 7];
+
+const BASE_UNITS: [&str; 7] = ["m", "kg", "s", "A", "K", "mol", "cd"];
+
+const UNITS: [(&str, f64, Dimension); 35] = [
+    // length
+    ("m", 1.0, [1, 0, 0, 0, 0, 0, 0]),
+    ("km", 1000.0, [1, 0, 0, 0, 0, 0, 0]),
+    ("cm", 0.01, [1, 0, 0, 0, 0, 0, 0]),
+    ("mm", 0.001, [1, 0, 0, 0, 0, 0, 0]),
+    ("mi", 1609.344, [1, 0, 0, 0, 0, 0, 0]),
+    ("yd", 0.9144, [1, 0, 0, 0, 0, 0, 0]),
+    ("ft", 0.3048, [1, 0, 0, 0, 0, 0, 0]),
+    // mass
+    ("kg", 1.0, [0, 1, 0, 0, 0, 0, 0]),
+    ("g", 0.001, [0, 1, 0, 0, 0, 0, 0]),
+    ("mg", 0.000001, [0, 1, 0, 0, 0, 0, 0]),
+    ("t", 1000.0, [0, 1, 0, 0, 0, 0, 0]),
+    ("lb", 0.45359237, [0, 1, 0, 0, 0, 0, 0]),
+    // time
+    ("s", 1.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("ms", 0.001, [0, 0, 1, 0, 0, 0, 0]),
+    ("min", 60.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("h", 3600.0, [0, 0, 1, 0, 0, 0, 0]),
+    ("day", 86400.0, [0, 0, 1, 0, 0, 0, 0]),
+    // remaining base units
+    ("A", 1.0, [0, 0, 0, 1, 0, 0, 0]),
+    ("K", 1.0, [0, 0, 0, 0, 1, 0, 0]),
+    ("mol", 1.0, [0, 0, 0, 0, 0, 1, 0]),
+    ("cd", 1.0, [0, 0, 0, 0, 0, 0, 1]),
+    // derived
+    ("L", 0.001, [3, 0, 0, 0, 0, 0, 0]),
+    ("mL", 0.000001, [3, 0, 0, 0, 0, 0, 0]),
+    ("Hz", 1.0, [0, 0, -1, 0, 0, 0, 0]),
+    ("N", 1.0, [1, 1, -2, 0, 0, 0, 0]),
+    ("Pa", 1.0, [-1, 1, -2, 0, 0, 0, 0]),
+    ("J", 1.0, [2, 1, -2, 0, 0, 0, 0]),
+    ("kJ", 1000.0, [2, 1, -2, 0, 0, 0, 0]),
+    ("W", 1.0, [2, 1, -3, 0, 0, 0, 0]),
+    ("kW", 1000.0, [2, 1, -3, 0, 0, 0, 0]),
+    ("kWh", 3600000.0, [2, 1, -2, 0, 0, 0, 0]),
+    ("C", 1.0, [0, 0, 1, 1, 0, 0, 0]),
+    ("V", 1.0, [2, 1, -3, -1, 0, 0, 0]),
+    ("Ohm", 1.0, [2, 1, -3, -2, 0, 0, 0]),
+    ("F", 1.0, [-2, -1, 4, 2, 0, 0, 0]),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Unit {
+    pub name: String,
+    pub factor: f64,
+    pub dimension: Dimension,
+}
+
+/// A measurement stored in SI base units, displayed in `unit`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+pub fn is_unit(name: &str) -> bool {
+    UNITS.iter().any(|(unit, _, _)| *unit == name)
+}
+
+impl Unit {
+    /// Parses unit expressions such as `km/h` or `kg*m/s^2`, where a `/` only
+    /// applies to the unit directly after it.
+    pub fn parse(text: &str) -> Unit {
+        let mut factor = 1.0;
+        let mut dimension = [0; 7];
+        let mut divide = false;
+        let mut start = 0;
+
+        for (i, c) in text.char_indices().chain([(text.len(), '*')]) {
+            if c != '*' && c != '/' {
+                continue;
+            }
+
+            let (name, exponent) = match text[start..i].split_once('^') {
+                Some((name, exponent)) => (
+                    name,
+                    exponent
+                        .parse::<i8>()
+                        .unwrap_or_else(|_| panic!("Invalid unit exponent in {}", text)),
+                ),
+                None => (&text[start..i], 1),
+            };
+            let exponent = if divide { -exponent } else { exponent };
+
+            let (_, unit_factor, unit_dimension) = UNITS
+                .iter()
+                .find(|(unit, _, _)| *unit == name)
+                .unwrap_or_else(|| panic!("Unknown unit {}", name));
+
+            factor *= unit_factor.powi(exponent as i32);
+            for (total, base) in dimension.iter_mut().zip(unit_dimension) {
+                *total += base * exponent;
+            }
+
+            divide = c == '/';
+            start = i + 1;
+        }
+
+        Unit {
+            name: text.to_string(),
+            factor,
+            dimension,
+        }
+    }
+
+    /// Builds the SI base unit for a dimension, e.g. `kg*m/s^2`.
+    pub fn from_dimension(dimension: Dimension) -> Unit {
+        let format = |name: &str, exponent: i8| {
+            if exponent == 1 {
+                name.to_string()
+            } else {
+                format!("{}^{}", name, exponent)
+            }
+        };
+
+        let numerator: Vec<String> = BASE_UNITS
+            .iter()
+            .zip(dimension)
+            .filter(|(_, exponent)| *exponent > 0)
+            .map(|(name, exponent)| format(name, exponent))
+            .collect();
+        let denominator: Vec<String> = BASE_UNITS
+            .iter()
+            .zip(dimension)
+            .filter(|(_, exponent)| *exponent < 0)
+            .map(|(name, exponent)| format(name, -exponent))
+            .collect();
+
+        let mut name = if numerator.is_empty() {
+            "1".to_string()
+        } else {
+            numerator.join("*")
+        };
+        for unit in denominator {
+            name += &format!("/{}", unit);
+        }
+
+        Unit {
+            name,
+            factor: 1.0,
+            dimension,
+        }
+    }
+}
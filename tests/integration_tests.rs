@@ -2,6 +2,8 @@ use calculator::{
     interpreter::interpreter::Interpreter, lexer::lexer::Lexer, parser::parser::Parser,
     token::token::Value,
 };
+use num_complex::Complex;
+use num_rational::BigRational;
 
 fn evaluate(input: String) -> Value {
     let mut lexer = Lexer::new(input);
@@ -15,6 +17,28 @@ fn evaluate(input: String) -> Value {
     return result;
 }
 
+fn evaluate_with_precision(input: String, precision: u32) -> Value {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_precision(precision);
+    evaluate_with(input, interpreter)
+}
+
+fn evaluate_with_complex_mode(input: String) -> Value {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_complex_mode(true);
+    evaluate_with(input, interpreter)
+}
+
+fn evaluate_with(input: String, mut interpreter: Interpreter) -> Value {
+    let mut lexer = Lexer::new(input);
+    let tokens = lexer.lex();
+
+    let mut parser = Parser::new(tokens);
+    let root = parser.parse();
+
+    interpreter.evaluate(root)
+}
+
 #[test]
 fn test_add() {
     let input = "let int x = 6; let int y = 6; x + y;".to_string();
@@ -407,3 +431,1416 @@ fn test_fibonacci_with_tail_call_optimization() {
     let input = "funk fibonacci(int x) { funk fib(int x, int a, int b) { if (x == 0) { a; } else { fib(x - 1, b, a + b); } } fib(x, 0, 1); } fibonacci(10);".to_string();
     assert_eq!(evaluate(input), Value::Number(55));
 }
+
+// logical operators
+
+#[test]
+fn test_logical_and() {
+    let input = "let int x = 6; x > 0 && x < 10;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_logical_or() {
+    let input = "let int x = 6; x < 0 || x == 6;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_logical_not() {
+    let input = "let bool x = false; !x && !(1 == 2);".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_logical_not_without_spaces() {
+    let input =
+        "let bool x = true; let list results = [true&&!false, !!x, 5&~1]; results;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Boolean(true),
+            Value::Boolean(true),
+            Value::Number(4)
+        ])
+    );
+}
+
+#[test]
+fn test_logical_precedence() {
+    let input = "true || false && false;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_logical_short_circuit() {
+    let input = "let list l = []; len(l) > 0 && head(l) == 1;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(false));
+}
+
+#[test]
+fn test_comparison_binds_looser_than_arithmetic() {
+    let input = "let int x = 5; x == 2 + 3;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+// floats
+
+#[test]
+fn test_float_literal() {
+    let input = "let float x = 2.75; x;".to_string();
+    assert_eq!(evaluate(input), Value::Float(2.75));
+}
+
+#[test]
+fn test_float_exponent_literal() {
+    let input = "1e-3 + 2.5E2;".to_string();
+    assert_eq!(evaluate(input), Value::Float(250.001));
+}
+
+#[test]
+fn test_mixed_arithmetic_promotes_to_float() {
+    let input = "let int total = 7; total / 2.0;".to_string();
+    assert_eq!(evaluate(input), Value::Float(3.5));
+}
+
+#[test]
+fn test_integer_division_stays_integer() {
+    let input = "7 / 2;".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+#[test]
+fn test_float_comparison() {
+    let input = "2 == 2.0 && 1.5 < 2 && -0.5 < 0;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_float_annotation_accepts_int() {
+    let input = "let float x = 2; x = x + 1; x;".to_string();
+    assert_eq!(evaluate(input), Value::Float(3.0));
+}
+
+#[test]
+fn test_identifier_with_digits() {
+    let input = "let int x1 = 1; let int x2 = 2; x1 + x2;".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+// big numbers
+
+#[test]
+fn test_overflow_promotes_to_big_number() {
+    let input = "2147483647 + 1;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::BigNumber("2147483648".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_big_factorial() {
+    let input =
+        "funk factorial(int x) { if (x == 0) { 1; } else { x * factorial(x - 1); } } factorial(20);"
+            .to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::BigNumber("2432902008176640000".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_big_number_demotes_when_small() {
+    let input = "let int x = 3000000000; x - 2999999999;".to_string();
+    assert_eq!(evaluate(input), Value::Number(1));
+}
+
+#[test]
+fn test_big_number_comparison() {
+    let input = "let int x = 3000000000; x > 2147483647 && x == 3000000000;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_type_with_big_number() {
+    let input = "type(99999999999 * 99999999999)".to_string();
+    assert_eq!(evaluate(input), Value::String("number".to_string()));
+}
+
+// rationals
+
+#[test]
+fn test_rational_addition_is_exact() {
+    let input = "rational(1, 3) + rational(1, 6) == rational(1, 2);".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_rational_with_integer() {
+    let input = "let rational x = rational(1, 4); x * 4 == 1;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_rational_to_float() {
+    let input = "to_float(rational(3, 4));".to_string();
+    assert_eq!(evaluate(input), Value::Float(0.75));
+}
+
+#[test]
+fn test_rational_mixed_with_float() {
+    let input = "rational(1, 2) + 0.25;".to_string();
+    assert_eq!(evaluate(input), Value::Float(0.75));
+}
+
+#[test]
+fn test_type_with_rational() {
+    let input = "type(rational(2, 4))".to_string();
+    assert_eq!(evaluate(input), Value::String("rational".to_string()));
+}
+
+// decimal mode
+
+fn decimal(numerator: i64, denominator: i64) -> Value {
+    Value::Decimal(BigRational::new(numerator.into(), denominator.into()))
+}
+
+#[test]
+fn test_decimal_addition_is_exact() {
+    let input = "0.1 + 0.2;".to_string();
+    assert_eq!(evaluate_with_precision(input, 50), decimal(3, 10));
+}
+
+#[test]
+fn test_decimal_equality() {
+    let input = "0.1 + 0.2 == 0.3;".to_string();
+    assert_eq!(evaluate_with_precision(input, 50), Value::Boolean(true));
+}
+
+#[test]
+fn test_decimal_mode_indexing_and_ranges() {
+    let input = "let list l = [1, 2, 3]; let int total = 0; for i in 0..3 { total = total + l[i]; } l[1] = 5; total + l[1];".to_string();
+    assert_eq!(evaluate_with_precision(input, 10), decimal(11, 1));
+}
+
+#[test]
+fn test_decimal_mode_counter_loop() {
+    let input = "let int i = 0; while i < 3 { i = i + 1; } i;".to_string();
+    assert_eq!(evaluate_with_precision(input, 10), Value::Number(3));
+}
+
+#[test]
+fn test_decimal_division_rounds_to_precision() {
+    let input = "2 / 3;".to_string();
+    assert_eq!(evaluate_with_precision(input, 4), decimal(6667, 10000));
+}
+
+#[test]
+fn test_decimal_exponent_literal() {
+    let input = "let decimal x = 1.5e-3; x * 1000;".to_string();
+    assert_eq!(evaluate_with_precision(input, 10), decimal(3, 2));
+}
+
+#[test]
+fn test_decimal_half_powers_use_full_precision() {
+    let input = "[2 ** 0.5, 2 ** 1.5, sqrt(2)];".to_string();
+    assert_eq!(
+        evaluate_with_precision(input, 10),
+        Value::List(vec![
+            decimal(14142135624, 10000000000),
+            decimal(28284271247, 10000000000),
+            decimal(14142135624, 10000000000),
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Decimal powers need an integer or half-integer exponent")]
+fn test_decimal_power_with_other_fraction() {
+    let input = "2 ** 0.3;".to_string();
+    evaluate_with_precision(input, 50);
+}
+
+#[test]
+fn test_decimal_int_annotation() {
+    let input = "let int x = 4; x;".to_string();
+    assert_eq!(evaluate_with_precision(input, 10), Value::Number(4));
+}
+
+// complex numbers
+
+#[test]
+fn test_imaginary_literal() {
+    let input = "3 + 4i;".to_string();
+    assert_eq!(evaluate(input), Value::Complex(Complex::new(3.0, 4.0)));
+}
+
+#[test]
+fn test_complex_multiplication() {
+    let input = "let complex z = 1 + 2i; z * z;".to_string();
+    assert_eq!(evaluate(input), Value::Complex(Complex::new(-3.0, 4.0)));
+}
+
+#[test]
+fn test_complex_reassignment_keeps_type() {
+    let input = "let complex z = 1i; z = 2; z;".to_string();
+    assert_eq!(evaluate(input), Value::Complex(Complex::new(2.0, 0.0)));
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_complex_reassignment_rejects_string() {
+    let input = "let complex z = 1i; z = \"hello\";".to_string();
+    evaluate(input);
+}
+
+#[test]
+fn test_complex_magnitude() {
+    let input = "abs(3 - 4i);".to_string();
+    assert_eq!(evaluate(input), Value::Float(5.0));
+}
+
+#[test]
+fn test_sqrt_of_negative_in_complex_mode() {
+    let input = "sqrt(-1) == 1i;".to_string();
+    assert_eq!(evaluate_with_complex_mode(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_unit_imaginary_part_prints_as_i() {
+    let input =
+        "[to_str(sqrt(-1)), to_str(-1i), to_str(2 - 1i), to_str(3 + 1i), to_str(2i)];".to_string();
+    assert_eq!(
+        evaluate_with_complex_mode(input),
+        Value::List(vec![
+            Value::String("i".to_string()),
+            Value::String("-i".to_string()),
+            Value::String("2-i".to_string()),
+            Value::String("3+i".to_string()),
+            Value::String("2i".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_negative_base_half_power_in_complex_mode() {
+    let input = "to_str((-8) ** 0.5);".to_string();
+    assert_eq!(
+        evaluate_with_complex_mode(input),
+        Value::String("2.828427i".to_string())
+    );
+}
+
+#[test]
+fn test_quadratic_with_complex_roots() {
+    let input = "let int b = 2; let int c = 5; (-b + sqrt(b * b - 4 * c)) / 2;".to_string();
+    assert_eq!(
+        evaluate_with_complex_mode(input),
+        Value::Complex(Complex::new(-1.0, 2.0))
+    );
+}
+
+#[test]
+#[should_panic(expected = "Cannot take the square root of a negative number")]
+fn test_sqrt_of_negative_without_complex_mode() {
+    let input = "sqrt(-1);".to_string();
+    evaluate(input);
+}
+
+// units of measure
+
+#[test]
+fn test_unit_named_variables_after_numbers() {
+    let input = "let int s = 0; let int n = 5; s = n; funk g(int x) { x } let int y = 1; g(y) + s;"
+        .to_string();
+    assert_eq!(evaluate(input), Value::Number(6));
+}
+
+#[test]
+fn test_unit_named_variable_starting_a_statement() {
+    let input = "let int g = 2; let int x = 5; g + x;".to_string();
+    assert_eq!(evaluate(input), Value::Number(7));
+}
+
+#[test]
+fn test_unit_named_variable_as_last_statement() {
+    let input = "let int t = 1; let int y = 4; t;".to_string();
+    assert_eq!(evaluate(input), Value::Number(1));
+}
+
+#[test]
+fn test_unit_addition_converts_to_left_unit() {
+    let input = "to(1 km + 500 m, m) == 1500 m;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_unit_conversion() {
+    let input = "let quantity v = to(100 km/h, m/s); v > 27.7 m/s && v < 27.8 m/s;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_derived_units() {
+    let input = "let quantity f = 2 kg * 9.81 m/s^2; f > 19.6 N && f < 19.7 N;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+#[test]
+fn test_dimensionless_ratio() {
+    let input = "6 m / 300 cm;".to_string();
+    assert_eq!(evaluate(input), Value::Float(2.0));
+}
+
+#[test]
+fn test_type_with_quantity() {
+    let input = "type(3 s)".to_string();
+    assert_eq!(evaluate(input), Value::String("quantity".to_string()));
+}
+
+#[test]
+#[should_panic(expected = "Incompatible units: m and s")]
+fn test_adding_incompatible_units() {
+    let input = "5 m + 3 s;".to_string();
+    evaluate(input);
+}
+
+// exponentiation
+
+#[test]
+fn test_power_is_right_associative() {
+    let input = "2 ** 3 ** 2;".to_string();
+    assert_eq!(evaluate(input), Value::Number(512));
+}
+
+#[test]
+fn test_power_binds_tighter_than_multiplication() {
+    let input = "3 * 2 ** 2 - -2 ** 2;".to_string();
+    assert_eq!(evaluate(input), Value::Number(16));
+}
+
+#[test]
+fn test_power_with_negative_exponent() {
+    let input = "2 ** -2;".to_string();
+    assert_eq!(evaluate(input), Value::Float(0.25));
+}
+
+#[test]
+fn test_power_with_negative_exponent_without_spaces() {
+    let input = "2**-1;".to_string();
+    assert_eq!(evaluate(input), Value::Float(0.5));
+}
+
+#[test]
+fn test_power_with_float() {
+    let input = "9 ** 0.5;".to_string();
+    assert_eq!(evaluate(input), Value::Float(3.0));
+}
+
+#[test]
+fn test_power_overflow_promotes_to_big_number() {
+    let input = "2 ** 64;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::BigNumber("18446744073709551616".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_rational_power() {
+    let input = "rational(2, 3) ** -2 == rational(9, 4);".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(true));
+}
+
+// bitwise operators
+
+#[test]
+fn test_radix_literals() {
+    let input = "0xFF + 0b1010 + 0o17;".to_string();
+    assert_eq!(evaluate(input), Value::Number(280));
+}
+
+#[test]
+fn test_bitwise_operators() {
+    let input = "(0xF0 | 0x0F) ^ (0xFF & 0x3C);".to_string();
+    assert_eq!(evaluate(input), Value::Number(0xC3));
+}
+
+#[test]
+fn test_bitwise_precedence() {
+    let input = "1 | 2 ^ 3 & 1;".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+#[test]
+fn test_shift_operators() {
+    let input = "(1 << 4) + (256 >> 2) + ~0;".to_string();
+    assert_eq!(evaluate(input), Value::Number(79));
+}
+
+#[test]
+fn test_shift_overflow_promotes_to_big_number() {
+    let input = "1 << 40;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::BigNumber("1099511627776".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_bin_and_hex_formatting() {
+    let input = "bin(10) + \" \" + hex(255);".to_string();
+    assert_eq!(evaluate(input), Value::String("0b1010 0xff".to_string()));
+}
+
+// for loops
+
+#[test]
+fn test_for_over_list() {
+    let input =
+        "let int total = 0; for x in [1, 2, 3, 4] { total = total + x; } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(10));
+}
+
+#[test]
+fn test_for_over_string() {
+    let input = "let list chars = []; for c in \"abc\" { chars = [c] + chars; } chars;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("c".to_string()),
+            Value::String("b".to_string()),
+            Value::String("a".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_for_over_map() {
+    let input = "let map m = { \"b\": 2, \"a\": 1 }; let str keys = \"\"; let int total = 0; for k, v in m { keys = keys + k; total = total + v; } [keys, total];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::String("ab".to_string()), Value::Number(3)])
+    );
+}
+
+#[test]
+fn test_for_variable_scoped_to_body() {
+    let input = "let int x = 7; for x in [1, 2] { x; } x;".to_string();
+    assert_eq!(evaluate(input), Value::Number(7));
+}
+
+#[test]
+fn test_for_return_from_function() {
+    let input = "funk first_even(list l) { for x in l { if x % 2 == 0 { return x; } } return 0; } first_even([3, 5, 8, 10]);".to_string();
+    assert_eq!(evaluate(input), Value::Number(8));
+}
+
+// break and continue
+
+#[test]
+fn test_break_exits_while() {
+    let input = "let int i = 0; while true { i = i + 1; if i == 5 { break; } } i;".to_string();
+    assert_eq!(evaluate(input), Value::Number(5));
+}
+
+#[test]
+fn test_continue_skips_iteration() {
+    let input = "let int total = 0; for x in [1, 2, 3, 4, 5] { if x % 2 == 0 { continue; } total = total + x; } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(9));
+}
+
+#[test]
+fn test_continue_in_while_rechecks_condition() {
+    let input = "let int i = 0; let int total = 0; while i < 6 { i = i + 1; if i > 3 { continue; } else { total = total + i; } } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(6));
+}
+
+#[test]
+fn test_labeled_break() {
+    let input = "let int count = 0; 'outer: for x in [1, 2, 3] { for y in [1, 2, 3] { if x * y == 4 { break 'outer; } count = count + 1; } } count;".to_string();
+    assert_eq!(evaluate(input), Value::Number(4));
+}
+
+#[test]
+fn test_labeled_continue() {
+    let input = "let int count = 0; 'outer: for x in [1, 2, 3] { for y in [1, 2, 3] { if y == 2 { continue 'outer; } count = count + 1; } } count;".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+#[test]
+#[should_panic(expected = "break or continue outside of a loop")]
+fn test_break_outside_loop() {
+    let input = "funk f() { break; 1; } f();".to_string();
+    evaluate(input);
+}
+
+// ranges
+
+#[test]
+fn test_range_value() {
+    let input = "0..5;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::Range {
+            start: 0,
+            end: 5,
+            inclusive: false
+        }
+    );
+}
+
+#[test]
+fn test_range_bounds_are_sums() {
+    let input = "let int n = 3; len(n - 2..=n * 2 + 1);".to_string();
+    assert_eq!(evaluate(input), Value::Number(7));
+}
+
+#[test]
+fn test_len_of_full_int_range() {
+    let input = "len(-2147483648..2147483647);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::BigNumber("4294967295".parse().unwrap())
+    );
+}
+
+#[test]
+fn test_range_index_and_len() {
+    let input = "let range r = 10..20; [r[3], len(r), len(5..=5), len(5..2)];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Number(13),
+            Value::Number(10),
+            Value::Number(1),
+            Value::Number(0),
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Index out of range")]
+fn test_range_index_out_of_bounds() {
+    let input = "let range r = 0..3; r[3];".to_string();
+    evaluate(input);
+}
+
+#[test]
+fn test_for_over_range() {
+    let input = "let int total = 0; for i in 1..=100 { total = total + i; } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(5050));
+}
+
+#[test]
+fn test_range_to_list() {
+    let input = "let list l = 1..4; l + to_list(4..=5);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List((1..=5).map(Value::Number).collect())
+    );
+}
+
+// closures
+
+#[test]
+fn test_closure_captures_parameter() {
+    let input = "funk make_adder(int n) { funk add(int x) { return x + n; } return add; } let function add5 = make_adder(5); add5(10);".to_string();
+    assert_eq!(evaluate(input), Value::Number(15));
+}
+
+#[test]
+fn test_closure_counter_keeps_state() {
+    let input = "funk make_counter() { let int count = 0; funk increment() { count = count + 1; return count; } return increment; } let function counter = make_counter(); counter(); counter(); counter();".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+#[test]
+fn test_closure_counters_are_independent() {
+    let input = "funk make_counter() { let int count = 0; funk increment() { count = count + 1; return count; } return increment; } let function first = make_counter(); let function second = make_counter(); first(); first(); let list counts = [first(), second()]; counts;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(3), Value::Number(1)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Identifier not found")]
+fn test_closure_does_not_see_caller_locals() {
+    let input = "funk read_secret() { return secret; } funk caller() { let int secret = 2; return read_secret(); } caller();".to_string();
+    evaluate(input);
+}
+
+#[test]
+fn test_nested_functions_are_mutually_visible() {
+    let input = "funk is_even(int n) { funk even(int k) { if k == 0 { return true; } return odd(k - 1); } funk odd(int k) { if k == 0 { return false; } return even(k - 1); } return even(n); } [is_even(10), is_even(7)];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Boolean(true), Value::Boolean(false)])
+    );
+}
+
+// anonymous functions
+
+#[test]
+fn test_lambda_assigned_and_called() {
+    let input = "let function double = funk(int x) { x * 2 }; double(21);".to_string();
+    assert_eq!(evaluate(input), Value::Number(42));
+}
+
+#[test]
+fn test_lambda_passed_as_argument() {
+    let input = "funk apply(function f, int x) { return f(x); } apply(funk(int x) { x + 1 }, 41);"
+        .to_string();
+    assert_eq!(evaluate(input), Value::Number(42));
+}
+
+#[test]
+fn test_lambda_returned_from_function() {
+    let input = "funk multiplier(int factor) { return funk(int x) { x * factor }; } let function triple = multiplier(3); triple(5);".to_string();
+    assert_eq!(evaluate(input), Value::Number(15));
+}
+
+#[test]
+fn test_lambdas_stored_in_list() {
+    let input = "let list operations = [funk(int x) { x + 1 }, funk(int x) { x * 10 }]; let int total = 0; for op in operations { total = total + op(2); } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(23));
+}
+
+#[test]
+fn test_call_any_expression() {
+    let input = "let list fs = [funk(int x) { x + 1 }, funk(int x) { x * 10 }]; funk make() { return funk(int x) { x * 100 }; } [fs[1](4), make()(2), (funk(int x) { x })(1)];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Number(40),
+            Value::Number(200),
+            Value::Number(1),
+        ])
+    );
+}
+
+#[test]
+fn test_parenthesized_statement_after_lambda() {
+    let input = "let function f = funk(int x) { x }; (2 + 3) * 2;".to_string();
+    assert_eq!(evaluate(input), Value::Number(10));
+}
+
+#[test]
+fn test_lambda_is_function() {
+    let input = "type(funk() { 1 });".to_string();
+    assert_eq!(evaluate(input), Value::String("function".to_string()));
+}
+
+// pipeline operator
+
+#[test]
+fn test_pipeline_inserts_first_argument() {
+    let input = "funk sub(int a, int b) { a - b } 10 |> sub(3);".to_string();
+    assert_eq!(evaluate(input), Value::Number(7));
+}
+
+#[test]
+fn test_pipeline_bare_function() {
+    let input = "funk double(int x) { x * 2 } 5 |> double;".to_string();
+    assert_eq!(evaluate(input), Value::Number(10));
+}
+
+#[test]
+fn test_pipeline_chains_left_to_right() {
+    let input = "funk double(int x) { x * 2 } funk add(int x, int y) { x + y } 1 + 2 |> double |> add(1) |> double;".to_string();
+    assert_eq!(evaluate(input), Value::Number(14));
+}
+
+#[test]
+fn test_pipeline_into_builtins() {
+    let input = "[1, 2, 3] |> tail() |> len();".to_string();
+    assert_eq!(evaluate(input), Value::Number(2));
+}
+
+#[test]
+fn test_pipeline_has_lowest_precedence() {
+    let input = "funk negate(bool b) { !b } 1 < 2 || false |> negate;".to_string();
+    assert_eq!(evaluate(input), Value::Boolean(false));
+}
+
+// index and field assignment
+
+#[test]
+fn test_list_index_assignment() {
+    let input = "let list l = [1, 2, 3]; l[1] = 20; l;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(1), Value::Number(20), Value::Number(3)])
+    );
+}
+
+#[test]
+fn test_map_field_assignment() {
+    let input =
+        "let map point = { \"x\": 1 }; point.x = 5; point.y = 7; point.x + point.y;".to_string();
+    assert_eq!(evaluate(input), Value::Number(12));
+}
+
+#[test]
+fn test_map_fields_named_after_keywords() {
+    let input = "let map node = {val: 1, next: 2, range: 3}; node.in = 4; node.next + node.range + node.in;".to_string();
+    assert_eq!(evaluate(input), Value::Number(9));
+}
+
+#[test]
+fn test_nested_target_assignment() {
+    let input = "let map config = { \"servers\": [{ \"port\": 80 }] }; config.servers[0].port = 8080; config.servers[0].port;".to_string();
+    assert_eq!(evaluate(input), Value::Number(8080));
+}
+
+#[test]
+fn test_index_assignment_in_function_updates_outer_list() {
+    let input = "let list l = [0, 0, 0]; funk mark(int i) { l[i] = 1; } mark(2); l;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(0), Value::Number(0), Value::Number(1)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_index_assignment_keeps_element_type() {
+    let input = "let list l = [1, 2]; l[0] = \"one\";".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Index out of range")]
+fn test_index_assignment_out_of_range() {
+    let input = "let list l = [1, 2]; l[2] = 3;".to_string();
+    evaluate(input);
+}
+
+// chained and computed access
+
+#[test]
+fn test_chained_field_access() {
+    let input = "let map config = { \"db\": { \"primary\": { \"host\": \"localhost\" } } }; config.db.primary.host;".to_string();
+    assert_eq!(evaluate(input), Value::String("localhost".to_string()));
+}
+
+#[test]
+fn test_computed_map_key() {
+    let input = "let map scores = { \"alice\": 3, \"bob\": 5 }; let str name = \"bo\" + \"b\"; scores[name];".to_string();
+    assert_eq!(evaluate(input), Value::Number(5));
+}
+
+#[test]
+fn test_mixed_access_chain() {
+    let input =
+        "let map data = { \"rows\": [[1, 2], [3, 4]] }; data.rows[1][0] + data[\"rows\"][0][1];"
+            .to_string();
+    assert_eq!(evaluate(input), Value::Number(5));
+}
+
+#[test]
+fn test_access_on_call_results_and_literals() {
+    let input = "funk make() { { \"items\": [10, 20, 30] } } make().items[2] + [4, 5, 6][1] + tail([7, 8])[0];".to_string();
+    assert_eq!(evaluate(input), Value::Number(43));
+}
+
+#[test]
+fn test_access_on_parenthesized_expression() {
+    let input = "let list a = [1]; let list b = [2, 3]; (a + b)[2];".to_string();
+    assert_eq!(evaluate(input), Value::Number(3));
+}
+
+#[test]
+#[should_panic(expected = "Key missing not found")]
+fn test_computed_map_key_missing() {
+    let input = "let map scores = { \"alice\": 3 }; scores[\"missing\"];".to_string();
+    evaluate(input);
+}
+
+// string escapes and interpolation
+
+#[test]
+fn test_string_escapes() {
+    let input = r#""a\tb\nc \"quoted\" back\\slash";"#.to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("a\tb\nc \"quoted\" back\\slash".to_string())
+    );
+}
+
+#[test]
+fn test_unicode_escape() {
+    let input = r#""caf\u{e9} \u{1F600}";"#.to_string();
+    assert_eq!(evaluate(input), Value::String("café 😀".to_string()));
+}
+
+#[test]
+fn test_string_keeps_digits_operators_and_slashes() {
+    let input = r#""http://x.io/a-1 + (b*2), {c}";"#.to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("http://x.io/a-1 + (b*2), {c}".to_string())
+    );
+}
+
+#[test]
+fn test_string_interpolation() {
+    let input = r#"let int a = 2; let int b = 3; "total: ${a + b}!";"#.to_string();
+    assert_eq!(evaluate(input), Value::String("total: 5!".to_string()));
+}
+
+#[test]
+fn test_interpolation_formats_values() {
+    let input = r#"let list l = [1, 2]; let map m2 = { "k": "v" }; "${l} ${m2["k"]} ${1 < 2} ${to_str(1.5)}";"#.to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("[1, 2] v true 1.5".to_string())
+    );
+}
+
+#[test]
+fn test_escaped_interpolation() {
+    let input = r#""cost: \${price}";"#.to_string();
+    assert_eq!(evaluate(input), Value::String("cost: ${price}".to_string()));
+}
+
+#[test]
+fn test_escaped_interpolation_without_closing_brace() {
+    let input = r#""open \${ brace" + "\${";"#.to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("open ${ brace${".to_string())
+    );
+}
+
+// match expressions
+
+#[test]
+fn test_match_literals_and_wildcard() {
+    let input = "funk name(int n) { match n { 0 => \"zero\", 1 => \"one\", -1 => \"minus one\", _ => \"many\" } } name(0) + \" \" + name(-1) + \" \" + name(7);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("zero minus one many".to_string())
+    );
+}
+
+#[test]
+fn test_match_list_with_rest() {
+    let input = "funk sum(list l) { match l { [] => 0, [h, ...rest] => h + sum(rest) } } sum([1, 2, 3, 4]);".to_string();
+    assert_eq!(evaluate(input), Value::Number(10));
+}
+
+#[test]
+fn test_match_map_pattern() {
+    let input = "let map user = { \"name\": \"ada\", \"role\": \"admin\" }; match user { { \"role\": \"guest\" } => \"welcome\", { name: n, role: \"admin\" } => \"hello \" + n, _ => \"?\" };".to_string();
+    assert_eq!(evaluate(input), Value::String("hello ada".to_string()));
+}
+
+#[test]
+fn test_match_type_patterns() {
+    let input = "funk kind(list items) { match items { [int x, str s] => x + len(s), [float f, ...rest] => len(rest), _ => -1 } } [kind([2, \"abc\"]), kind([1.5, 1, 2]), kind([true])];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(5), Value::Number(2), Value::Number(-1)])
+    );
+}
+
+#[test]
+fn test_match_guards() {
+    let input = "funk sign(int n) { match n { x if x < 0 => \"negative\", 0 => \"zero\", _ => \"positive\" } } sign(-4) + sign(0) + sign(9);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("negativezeropositive".to_string())
+    );
+}
+
+#[test]
+fn test_match_arm_bindings_are_scoped() {
+    let input =
+        "let int total = 0; match [5] { [x] => { total = total + x; } }; total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(5));
+}
+
+#[test]
+#[should_panic(expected = "No match arm for value 3")]
+fn test_match_without_matching_arm() {
+    let input = "match 3 { 1 => true, 2 => false };".to_string();
+    evaluate(input);
+}
+
+// destructuring let
+
+#[test]
+fn test_destructure_list() {
+    let input = "let [a, b, ...rest] = [1, 2, 3, 4]; let list result = [a + b, len(rest)]; result;"
+        .to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(3), Value::Number(2)])
+    );
+}
+
+#[test]
+fn test_destructure_map_shorthand() {
+    let input =
+        "let map point = { \"x\": 3, \"y\": 4, \"z\": 0 }; let {x, y} = point; x * y;".to_string();
+    assert_eq!(evaluate(input), Value::Number(12));
+}
+
+#[test]
+fn test_destructure_nested_with_renaming() {
+    let input = "let { name: n, tags: [first, ..._] } = { \"name\": \"crate\", \"tags\": [\"rust\", \"cli\"] }; n + \"/\" + first;".to_string();
+    assert_eq!(evaluate(input), Value::String("crate/rust".to_string()));
+}
+
+#[test]
+fn test_destructure_inside_function() {
+    let input = "funk swap(list pair) { let [a, b] = pair; let list swapped = [b, a]; swapped } swap([1, 2]);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::Number(2), Value::Number(1)])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Cannot destructure [1]: value does not match the pattern")]
+fn test_destructure_shape_mismatch() {
+    let input = "let [a, b] = [1];".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Identifier a already exists")]
+fn test_destructure_existing_identifier() {
+    let input = "let int a = 1; let [a] = [2];".to_string();
+    evaluate(input);
+}
+
+// structs
+
+#[test]
+fn test_struct_construction_and_field_access() {
+    let input = "struct Point { int x, int y } let Point p = Point { y: 2, x: 1 }; p.x * 10 + p.y;"
+        .to_string();
+    assert_eq!(evaluate(input), Value::Number(12));
+}
+
+#[test]
+fn test_struct_type_and_parameter() {
+    let input = "struct Point { int x, int y } funk norm(Point p) { return p.x * p.x + p.y * p.y; } let Point p = Point { x: 3, y: 4 }; type(p) + \":\" + to_str(norm(p));".to_string();
+    assert_eq!(evaluate(input), Value::String("Point:25".to_string()));
+}
+
+#[test]
+fn test_struct_nested_field_assignment() {
+    let input = "struct Point { int x, int y } struct Line { Point start, Point end } let Line l = Line { start: Point { x: 0, y: 0 }, end: Point { x: 1, y: 1 } }; l.end.y = 5; to_str(l);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String(
+            "Line { start: Point { x: 0, y: 0 }, end: Point { x: 1, y: 5 } }".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_struct_match_patterns() {
+    let input = "struct Point { int x, int y } let Point p = Point { x: 1, y: 2 }; match p { {x: 0} => 0, Point q => q.y, _ => 3 };".to_string();
+    assert_eq!(evaluate(input), Value::Number(2));
+}
+
+#[test]
+fn test_struct_equality() {
+    let input = "struct Point { int x, int y } enum Shape { Dot(Point) } let Point p = Point { x: 1, y: 2 }; let list results = [p == Point { y: 2, x: 1 }, p == Point { x: 1, y: 3 }, Shape.Dot(p) == Shape.Dot(Point { x: 1, y: 2 })]; results;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::Boolean(true)
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "Unknown field z for struct Point")]
+fn test_struct_unknown_field() {
+    let input =
+        "struct Point { int x, int y } let Point p = Point { x: 1, y: 2, z: 3 };".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Missing field y for struct Point")]
+fn test_struct_missing_field() {
+    let input = "struct Point { int x, int y } let Point p = Point { x: 1 };".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_struct_rejects_map() {
+    let input = "struct Point { int x, int y } let Point p = { \"x\": 1, \"y\": 2 };".to_string();
+    evaluate(input);
+}
+
+// enums
+
+#[test]
+fn test_enum_construction_and_printing() {
+    let input = "enum Shape { Circle(float), Rect(float, float), Empty } let list shapes = [Shape.Rect(1, 2.5), Shape.Empty]; to_str(shapes);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("[Shape.Rect(1, 2.5), Shape.Empty]".to_string())
+    );
+}
+
+#[test]
+fn test_enum_value_and_type() {
+    let input = "enum Shape { Circle(float), Empty } let Shape s = Shape.Circle(2); s;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::Enum {
+            name: "Shape".to_string(),
+            variant: "Circle".to_string(),
+            payload: vec![Value::Float(2.0)],
+        }
+    );
+    let input = "enum Shape { Circle(float), Empty } type(Shape.Empty);".to_string();
+    assert_eq!(evaluate(input), Value::String("Shape".to_string()));
+}
+
+#[test]
+fn test_enum_equality() {
+    let input = "enum Shape { Circle(float), Empty } let list results = [Shape.Circle(1) == Shape.Circle(1.0), Shape.Circle(1) == Shape.Circle(2), Shape.Empty != Shape.Circle(1)]; results;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Boolean(true),
+            Value::Boolean(false),
+            Value::Boolean(true)
+        ])
+    );
+}
+
+#[test]
+fn test_enum_match_recursive() {
+    let input = "enum Expr { Num(int), Add(Expr, Expr) } funk eval(Expr e) { return match e { Expr.Num(n) => n, Expr.Add(a, b) => eval(a) + eval(b) }; } eval(Expr.Add(Expr.Num(1), Expr.Add(Expr.Num(2), Expr.Num(3))));".to_string();
+    assert_eq!(evaluate(input), Value::Number(6));
+}
+
+#[test]
+#[should_panic(expected = "Unknown variant Square for enum Shape")]
+fn test_enum_unknown_variant() {
+    let input = "enum Shape { Circle(float) } Shape.Square(1);".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Variant Shape.Circle expects 1 values, got 2")]
+fn test_enum_payload_arity() {
+    let input = "enum Shape { Circle(float) } Shape.Circle(1, 2);".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_enum_payload_type() {
+    let input = "enum Shape { Circle(float) } Shape.Circle(\"big\");".to_string();
+    evaluate(input);
+}
+
+// imports
+
+fn evaluate_files(test: &str, files: &[(&str, &str)]) -> Value {
+    let directory = std::env::temp_dir().join(format!("calculator_{}", test));
+    for (name, contents) in files {
+        let path = directory.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    let main = directory.join(files[0].0);
+    let mut interpreter = Interpreter::new();
+    interpreter.set_path(main.to_str().unwrap());
+    evaluate_with(std::fs::read_to_string(main).unwrap(), interpreter)
+}
+
+#[test]
+fn test_import_namespace() {
+    let result = evaluate_files(
+        "import_namespace",
+        &[
+            ("main.calc", "import utils; utils.double(utils.base);"),
+            (
+                "utils.calc",
+                "let int base = 21; funk double(int x) { return x * 2; }",
+            ),
+        ],
+    );
+    assert_eq!(result, Value::Number(42));
+}
+
+#[test]
+fn test_import_relative_to_importing_file() {
+    let result = evaluate_files(
+        "import_relative",
+        &[
+            ("main.calc", "import \"lib/shapes.calc\"; shapes.area(3);"),
+            (
+                "lib/shapes.calc",
+                "import \"../math.calc\"; funk area(int r) { return math.square(r) * 3; }",
+            ),
+            ("math.calc", "funk square(int x) { return x * x; }"),
+        ],
+    );
+    assert_eq!(result, Value::Number(27));
+}
+
+#[test]
+fn test_import_evaluates_once() {
+    let result = evaluate_files(
+        "import_once",
+        &[
+            (
+                "main.calc",
+                "import first; import second; first.shared.origin.x + second.shared.origin.y;",
+            ),
+            ("first.calc", "import shared;"),
+            ("second.calc", "import shared;"),
+            (
+                "shared.calc",
+                "struct Point { int x, int y } let Point origin = Point { x: 1, y: 2 };",
+            ),
+        ],
+    );
+    assert_eq!(result, Value::Number(3));
+}
+
+#[test]
+fn test_import_modules_with_same_type_names() {
+    let result = evaluate_files(
+        "import_same_types",
+        &[
+            (
+                "main.calc",
+                "struct P { int x } import a; import b; let P p = P { x: 1 }; p.x + a.make().x + b.area(b.make());",
+            ),
+            (
+                "a.calc",
+                "struct P { int x } funk make() { return P { x: 10 }; }",
+            ),
+            (
+                "b.calc",
+                "enum P { Square(int) } funk make() { return P.Square(10); } funk area(P shape) { return match shape { P.Square(side) => side * side }; }",
+            ),
+        ],
+    );
+    assert_eq!(result, Value::Number(111));
+}
+
+#[test]
+fn test_import_qualified_type_names() {
+    let result = evaluate_files(
+        "import_qualified_types",
+        &[
+            (
+                "main.calc",
+                "import utils; funk sum(utils.Point p) { return p.x + p.y; } let utils.Point p = utils.mk(3); let utils.Point q = utils.Point { x: 1, y: 2 }; let int side = match utils.square(5) { utils.Shape s => utils.area(s) }; sum(p) + sum(q) + side;",
+            ),
+            (
+                "utils.calc",
+                "struct Point { int x, int y } enum Shape { Square(int) } funk mk(int n) { return Point { x: n, y: n }; } funk square(int n) { return Shape.Square(n); } funk area(Shape s) { return match s { Shape.Square(side) => side * side }; }",
+            ),
+        ],
+    );
+    assert_eq!(result, Value::Number(34));
+}
+
+#[test]
+#[should_panic(expected = "Import cycle detected")]
+fn test_import_cycle() {
+    evaluate_files(
+        "import_cycle",
+        &[
+            ("main.calc", "import a;"),
+            ("a.calc", "import b;"),
+            ("b.calc", "import a;"),
+        ],
+    );
+}
+
+#[test]
+#[should_panic(expected = "Module missing.calc not found")]
+fn test_import_missing_module() {
+    evaluate_files("import_missing", &[("main.calc", "import missing;")]);
+}
+
+// exceptions
+
+#[test]
+fn test_try_catch_thrown_value() {
+    let input = "let int result = 0; try { throw { \"code\": 42 }; result = 1; } catch e { result = e.code; } result;".to_string();
+    assert_eq!(evaluate(input), Value::Number(42));
+}
+
+#[test]
+fn test_try_catch_runtime_errors() {
+    let input = "let list errors = []; try { missing; } catch e { errors = errors + [e]; } try { let list xs = [1]; xs[3]; } catch e { errors = errors + [e]; } try { let int x = \"a\"; } catch e { errors = errors + [e]; } errors;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("Identifier not found".to_string()),
+            Value::String("Index out of range".to_string()),
+            Value::String("Type mismatch".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_throw_unwinds_function_calls() {
+    let input = "funk check(int n) { if n > 2 { throw \"too big\"; } return n; } funk sum(list xs) { let int total = 0; for x in xs { total = total + check(x); } return total; } let str message = \"\"; try { sum([1, 2, 3]); } catch e { message = e; } message;".to_string();
+    assert_eq!(evaluate(input), Value::String("too big".to_string()));
+}
+
+#[test]
+fn test_finally_runs_on_success_error_and_return() {
+    let input = "let list log = []; funk early() { try { return 1; } finally { log = log + [\"return\"]; } } early(); try { log = log + [\"body\"]; } finally { log = log + [\"ok\"]; } try { try { throw 1; } finally { log = log + [\"error\"]; } } catch { } log;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("return".to_string()),
+            Value::String("body".to_string()),
+            Value::String("ok".to_string()),
+            Value::String("error".to_string()),
+        ])
+    );
+}
+
+#[test]
+fn test_try_is_an_expression() {
+    let input = "let int value = try { 10 / 0 } catch { -1 }; value;".to_string();
+    assert_eq!(evaluate(input), Value::Number(-1));
+}
+
+#[test]
+#[should_panic(expected = "Uncaught exception: boom")]
+fn test_uncaught_throw() {
+    let input = "try { throw \"boom\"; } finally { 1; }".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "Identifier not found")]
+fn test_error_rethrown_from_catch() {
+    let input = "try { throw 1; } catch e { missing; }".to_string();
+    evaluate(input);
+}
+
+#[test]
+fn test_try_catch_empty_head_and_tail() {
+    let input = "let list errors = []; try { head([]); } catch e { errors = errors + [e]; } try { tail([]); } catch e { errors = errors + [e]; } try { tail(\"\"); } catch e { errors = errors + [e]; } errors;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![Value::String("Index out of range".to_string()); 3])
+    );
+}
+
+#[test]
+fn test_try_catch_invalid_numeric_arguments() {
+    let input = "let list errors = []; for f in [funk() { 1.5 & 1 }, funk() { 1 >> -1 }, funk() { ~1.5 }, funk() { bin(1.5) }, funk() { hex(1.5) }, funk() { rational(1.5, 0.5) }, funk() { rational(1e39) }, funk() { (2 m) ** 1.5 }] { try { f(); } catch e { errors = errors + [e]; } } errors;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("Expected an integer".to_string()),
+            Value::String("Invalid shift amount".to_string()),
+            Value::String("Expected an integer".to_string()),
+            Value::String("Expected an integer".to_string()),
+            Value::String("Expected an integer".to_string()),
+            Value::String("Expected an integer".to_string()),
+            Value::String("Expected a finite number".to_string()),
+            Value::String("Quantities can only be raised to integer powers".to_string()),
+        ])
+    );
+}
+
+// generators
+
+#[test]
+fn test_generator_for_loop() {
+    let input = "funk* nums() { yield 1; yield 2; yield 3; } let int total = 0; for n in nums() { total = total + n; } total;".to_string();
+    assert_eq!(evaluate(input), Value::Number(6));
+}
+
+#[test]
+fn test_generator_next_until_exhausted() {
+    let input = "funk* pair() { yield \"a\"; yield \"b\"; } let generator g = pair(); let list items = [next(g), next(g), next(g)]; items;".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+            Value::Null
+        ])
+    );
+}
+
+#[test]
+fn test_generator_suspends_inside_loops_and_branches() {
+    let input = "funk* evens_and_odds(int stop) { let int i = 0; while i < stop { if i % 2 == 0 { yield i; } else { yield -i; } i = i + 1; } for c in \"ab\" { yield c; } } to_str(to_list(evens_and_odds(4)));".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("[0, -1, 2, -3, a, b]".to_string())
+    );
+}
+
+#[test]
+fn test_generator_is_lazy() {
+    let input = "funk* naturals() { let int n = 0; while true { yield n; n = n + 1; } } funk* take(generator source, int count) { for value in source { if count == 0 { return 0; } yield value * value; count = count - 1; } } to_list(take(naturals(), 4));".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::Number(0),
+            Value::Number(1),
+            Value::Number(4),
+            Value::Number(9)
+        ])
+    );
+}
+
+#[test]
+fn test_generator_keeps_state_between_pulls() {
+    let input = "let function counter = funk*(int start) { for i in start..start + 10 { match i % 3 { 0 => { yield \"fizz\"; }, _ => { yield i; } }; } }; let generator g = counter(2); next(g); let str third = next(g); let int fourth = next(g); third + to_str(fourth) + \":\" + type(g);".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::String("fizz4:generator".to_string())
+    );
+}
+
+#[test]
+fn test_generator_finishes_after_throw() {
+    let input = "funk* g() { yield 1; throw \"boom\"; yield 2; } let generator it = g(); next(it); let str error = \"\"; try { next(it); } catch e { error = e; } [error, next(it), next(it)];".to_string();
+    assert_eq!(
+        evaluate(input),
+        Value::List(vec![
+            Value::String("boom".to_string()),
+            Value::Null,
+            Value::Null,
+        ])
+    );
+}
+
+#[test]
+#[should_panic(expected = "yield outside of a generator")]
+fn test_yield_outside_generator() {
+    let input = "funk f() { yield 1; } f();".to_string();
+    evaluate(input);
+}
+
+#[test]
+#[should_panic(expected = "yield can only be used as a statement inside a generator")]
+fn test_yield_in_expression_position() {
+    let input = "funk* f() { let int x = yield 1; } to_list(f());".to_string();
+    evaluate(input);
+}